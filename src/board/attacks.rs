@@ -0,0 +1,129 @@
+use crate::Board;
+use crate::board::bitboard::{
+    bishop_attacks, king_attacks, knight_attacks, pawn_attacks, rook_attacks, squares, Bitboard,
+};
+use crate::board::board::{Color, Piece};
+use crate::board::square::Square;
+
+impl Board {
+    /// Every piece of either color that attacks `square`, whether or not anything stands on it.
+    pub fn attackers_to(&self, square: Square) -> Bitboard {
+        let occupied = self.occupied();
+        let diagonal = self.pieces[Piece::B as usize] | self.pieces[Piece::Q as usize];
+        let straight = self.pieces[Piece::R as usize] | self.pieces[Piece::Q as usize];
+
+        // look outwards from the square with each kind of piece, whatever we can see can see us back.
+        // pawns are the one piece that isn't symmetric, a white pawn attacks us from where a black one on our square would attack
+        knight_attacks(square) & self.pieces[Piece::N as usize]
+            | king_attacks(square) & self.pieces[Piece::K as usize]
+            | pawn_attacks(Color::Black, square) & self.pieces_of(Piece::P, Color::White)
+            | pawn_attacks(Color::White, square) & self.pieces_of(Piece::P, Color::Black)
+            | bishop_attacks(square, occupied) & diagonal
+            | rook_attacks(square, occupied) & straight
+    }
+
+    /// Whether any piece of color `by` attacks `square`.
+    pub fn is_square_attacked(&self, square: Square, by: Color) -> bool {
+        self.attackers_to(square) & self.colors[by as usize] != 0
+    }
+
+    /// Where the king of `color` stands, read off the bitboards so it can't go stale. `None` only on a board
+    /// that was set up without one.
+    pub fn king_square(&self, color: Color) -> Option<Square> {
+        squares(self.pieces_of(Piece::K, color)).next()
+    }
+
+    /// Whether the king of `color` is attacked by any of the other side's pieces.
+    pub fn in_check(&self, color: Color) -> bool {
+        self.king_square(color).is_some_and(|king| self.is_square_attacked(king, !color))
+    }
+
+    /// Every square the pieces of `color` attack, empty or not and whichever side is on it.
+    pub fn attack_map(&self, color: Color) -> Bitboard {
+        let occupied = self.occupied();
+        let mut attacks = 0;
+
+        for square in squares(self.colors[color as usize]) {
+            attacks |= match self.piece_at(square) {
+                Some((Piece::P, _)) => pawn_attacks(color, square),
+                Some((Piece::N, _)) => knight_attacks(square),
+                Some((Piece::B, _)) => bishop_attacks(square, occupied),
+                Some((Piece::R, _)) => rook_attacks(square, occupied),
+                Some((Piece::Q, _)) => bishop_attacks(square, occupied) | rook_attacks(square, occupied),
+                Some((Piece::K, _)) => king_attacks(square),
+                None => 0,
+            };
+        }
+
+        attacks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Board, Color, Move, Square};
+    use crate::board::bitboard::{bit, squares};
+
+    fn square(name: &str) -> Square {
+        name.parse().unwrap()
+    }
+
+    #[test]
+    fn pawns_attack_empty_squares() {
+        let board = Board::new();
+
+        assert!(board.is_square_attacked(square("d3"), Color::White));
+        assert!(board.is_square_attacked(square("f6"), Color::Black));
+        assert!(!board.is_square_attacked(square("e4"), Color::White)); // pawns push there but don't attack it
+        assert!(!board.is_square_attacked(square("e5"), Color::White));
+    }
+
+    #[test]
+    fn attackers_come_from_both_sides() {
+        // the e4 pawn is hit by the d5 pawn, the f6 knight and the b7 bishop, and defended by the c3 knight and e2 queen
+        let board = Board::from_fen("rn2kb1r/pbp1pppp/1p3n2/3p4/4P3/2N5/PPPPQPPP/R1B1KBNR w KQkq - 0 1").unwrap();
+        let attackers: Vec<String> = squares(board.attackers_to(square("e4"))).map(|square| square.to_string()).collect();
+
+        assert_eq!(attackers, ["e2", "c3", "d5", "f6"]);
+        assert!(board.attackers_to(square("e4")) & bit(square("b7")) == 0); // the d5 pawn is in the way
+    }
+
+    #[test]
+    fn attack_map_matches_is_square_attacked() {
+        let board = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+
+        for color in [Color::White, Color::Black] {
+            let map = board.attack_map(color);
+            for square in (0..64).map(Square::new) {
+                assert_eq!(map & bit(square) != 0, board.is_square_attacked(square, color), "{} for {:?}", square, color);
+            }
+        }
+    }
+
+    #[test]
+    fn kings_are_found_after_every_kind_of_move() {
+        let mut board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(board.king_square(Color::White), Some(square("e1")));
+        assert_eq!(board.king_square(Color::Black), Some(square("e8")));
+
+        board.push_move(Move::new(square("e1"), square("g1"), Move::KING_CASTLE));
+        board.push_move(Move::new(square("e8"), square("d8"), Move::QUIET));
+        assert_eq!(board.king_square(Color::White), Some(square("g1")));
+        assert_eq!(board.king_square(Color::Black), Some(square("d8")));
+
+        board.pop_move();
+        board.pop_move();
+        assert_eq!(board.king_square(Color::White), Some(square("e1")));
+        assert_eq!(board.king_square(Color::Black), Some(square("e8")));
+    }
+
+    #[test]
+    fn in_check_looks_at_the_right_king() {
+        let board = Board::from_fen("4k3/8/8/1B6/8/8/8/4K3 b - - 0 1").unwrap();
+
+        assert!(board.in_check(Color::Black));
+        assert!(!board.in_check(Color::White));
+        assert!(!Board::new().in_check(Color::White));
+        assert!(!Board::empty().in_check(Color::White));
+    }
+}
@@ -0,0 +1,226 @@
+use std::sync::OnceLock;
+
+use crate::board::board::Color;
+use crate::board::square::Square;
+
+// one bit per square, bit 0 is a1 and bit 63 is h8 just like the square numbering in square.rs
+/// A set of squares, one bit per square with bit 0 being a1 and bit 63 h8.
+pub type Bitboard = u64;
+
+const ROOK_DIRECTIONS: [(isize, isize); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const BISHOP_DIRECTIONS: [(isize, isize); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+/// The bitboard holding just `square`.
+pub fn bit(square: Square) -> Bitboard {
+    1 << square.index()
+}
+
+/// Iterates over the set bits from a1 upwards.
+pub struct Squares(Bitboard);
+
+impl Iterator for Squares {
+    type Item = Square;
+
+    fn next(&mut self) -> Option<Square> {
+        if self.0 == 0 {
+            return None;
+        }
+
+        let square = Square::new(self.0.trailing_zeros() as usize);
+        self.0 &= self.0 - 1;
+        Some(square)
+    }
+}
+
+/// The squares in a bitboard, from a1 upwards.
+pub fn squares(bitboard: Bitboard) -> Squares {
+    Squares(bitboard)
+}
+
+struct Magic {
+    mask: Bitboard, // squares whose occupancy matters, the board edges never block anything so they're left out
+    magic: u64,
+    shift: u32,
+    offset: usize, // where this square's attacks start in AttackTables::sliding
+}
+
+struct AttackTables {
+    knight: [Bitboard; 64],
+    king: [Bitboard; 64],
+    pawn: [[Bitboard; 64]; 2], // indexed by color
+    rook: Vec<Magic>,
+    bishop: Vec<Magic>,
+    sliding: Vec<Bitboard>,
+}
+
+static TABLES: OnceLock<AttackTables> = OnceLock::new();
+
+fn tables() -> &'static AttackTables {
+    TABLES.get_or_init(AttackTables::new)
+}
+
+/// The squares a knight on `square` attacks.
+pub fn knight_attacks(square: Square) -> Bitboard {
+    tables().knight[square.index()]
+}
+
+/// The squares a king on `square` attacks.
+pub fn king_attacks(square: Square) -> Bitboard {
+    tables().king[square.index()]
+}
+
+/// The squares a pawn of this color on this square captures on.
+pub fn pawn_attacks(color: Color, square: Square) -> Bitboard {
+    tables().pawn[color as usize][square.index()]
+}
+
+/// The squares a rook on `square` attacks, stopping at the first piece in `occupied` each way.
+pub fn rook_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    let tables = tables();
+    tables.sliding[tables.rook[square.index()].index(occupied)]
+}
+
+/// The squares a bishop on `square` attacks, stopping at the first piece in `occupied` each way.
+pub fn bishop_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    let tables = tables();
+    tables.sliding[tables.bishop[square.index()].index(occupied)]
+}
+
+/// Rook and bishop attacks together.
+pub fn queen_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    rook_attacks(square, occupied) | bishop_attacks(square, occupied)
+}
+
+impl Magic {
+    fn index(&self, occupied: Bitboard) -> usize {
+        self.offset + ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+impl AttackTables {
+    fn new() -> Self {
+        let mut knight = [0; 64];
+        let mut king = [0; 64];
+        let mut pawn = [[0; 64]; 2];
+
+        for index in 0..64 {
+            let square = Square::new(index);
+
+            for (rank_delta, file_delta) in [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)] {
+                knight[index] |= step(square, rank_delta, file_delta);
+            }
+
+            for (rank_delta, file_delta) in ROOK_DIRECTIONS.iter().chain(BISHOP_DIRECTIONS.iter()) {
+                king[index] |= step(square, *rank_delta, *file_delta);
+            }
+
+            pawn[Color::White as usize][index] = step(square, 1, -1) | step(square, 1, 1);
+            pawn[Color::Black as usize][index] = step(square, -1, -1) | step(square, -1, 1);
+        }
+
+        let mut sliding = Vec::new();
+        let mut rng = XorShift(0x9e37_79b9_7f4a_7c15); // fixed seed so the same magics come out every run
+        let rook = (0..64).map(|index| find_magic(Square::new(index), &ROOK_DIRECTIONS, &mut sliding, &mut rng)).collect();
+        let bishop = (0..64).map(|index| find_magic(Square::new(index), &BISHOP_DIRECTIONS, &mut sliding, &mut rng)).collect();
+
+        AttackTables { knight, king, pawn, rook, bishop, sliding }
+    }
+}
+
+fn step(square: Square, rank_delta: isize, file_delta: isize) -> Bitboard { // empty if it would leave the board
+    square.offset(file_delta, rank_delta).map_or(0, bit)
+}
+
+fn slow_attacks(square: Square, directions: &[(isize, isize)], occupied: Bitboard) -> Bitboard { // walks each ray, only used to fill the tables
+    let mut attacks = 0;
+
+    for &(rank_delta, file_delta) in directions {
+        let mut current = square;
+        while let Some(next) = current.offset(file_delta, rank_delta) {
+            attacks |= bit(next);
+            if occupied & bit(next) != 0 {
+                break;
+            }
+            current = next;
+        }
+    }
+
+    attacks
+}
+
+fn relevant_mask(square: Square, directions: &[(isize, isize)]) -> Bitboard {
+    let mut mask = 0;
+
+    for &(rank_delta, file_delta) in directions {
+        let mut current = square;
+        // stop one short of the edge, what sits on the last square of a ray doesn't change anything
+        while let Some(next) = current.offset(file_delta, rank_delta).filter(|next| next.offset(file_delta, rank_delta).is_some()) {
+            mask |= bit(next);
+            current = next;
+        }
+    }
+
+    mask
+}
+
+fn find_magic(square: Square, directions: &[(isize, isize)], sliding: &mut Vec<Bitboard>, rng: &mut XorShift) -> Magic {
+    let mask = relevant_mask(square, directions);
+    let bits = mask.count_ones();
+    let shift = 64 - bits;
+
+    // every subset of the mask, walked with the carry-rippler trick
+    let mut occupancies = Vec::with_capacity(1 << bits);
+    let mut subset: Bitboard = 0;
+    loop {
+        occupancies.push((subset, slow_attacks(square, directions, subset)));
+        subset = subset.wrapping_sub(mask) & mask;
+        if subset == 0 {
+            break;
+        }
+    }
+
+    let mut table = vec![0; 1 << bits];
+    let mut used = vec![0u32; 1 << bits]; // which attempt last wrote each slot, saves clearing the table between attempts
+    let mut attempt = 0;
+
+    loop {
+        let magic = rng.sparse();
+        if (mask.wrapping_mul(magic) >> 56).count_ones() < 6 { // not enough high bits to ever spread the mask out
+            continue;
+        }
+
+        attempt += 1;
+        let mut fits = true;
+        for &(occupied, attacks) in &occupancies {
+            let index = (occupied.wrapping_mul(magic) >> shift) as usize;
+            if used[index] != attempt {
+                used[index] = attempt;
+                table[index] = attacks;
+            } else if table[index] != attacks {
+                fits = false;
+                break;
+            }
+        }
+
+        if fits {
+            let offset = sliding.len();
+            sliding.extend_from_slice(&table);
+            return Magic { mask, magic, shift, offset };
+        }
+    }
+}
+
+pub(crate) struct XorShift(pub(crate) u64); // small and fast, plenty random enough for magics
+
+impl XorShift {
+    pub(crate) fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn sparse(&mut self) -> u64 { // magics with few bits set are found much quicker
+        self.next() & self.next() & self.next()
+    }
+}
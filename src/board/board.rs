@@ -1,3 +1,28 @@
+use std::ops::Not;
+
+use crate::board::bitboard::Bitboard;
+use crate::board::square::Square;
+use crate::moves::Move;
+
+/// A side, `!color` is the other one. `color as usize` is the index into `Board::colors`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Color {
+    White,
+    Black,
+}
+
+impl Not for Color {
+    type Output = Color;
+
+    fn not(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+}
+
+/// A kind of piece, its color is kept alongside. The order matters, it's the index into `Board::pieces`.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Piece {
     K,
@@ -8,24 +33,45 @@ pub enum Piece {
     P,
 }
 
-#[derive(Debug, Clone, Copy)]
-pub struct Square {
-    pub piece: Option<(Piece, bool)>,
+/// Which castling moves each side still has the right to, whether or not they're possible right now.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CastlingRights {
+    pub w_kingside: bool,
+    pub w_queenside: bool,
+    pub b_kingside: bool,
+    pub b_queenside: bool,
 }
 
+/// Whatever `make_move` can't work backwards from the move itself, for `unmake_move`.
 #[derive(Debug, Clone, Copy)]
-pub struct LastMove {
-    pub position: (usize, usize),
-    pub piece: Piece,
-    pub color: bool,
+pub struct UndoInfo {
+    pub captured: Option<(Piece, Color)>,
+    pub castling: CastlingRights,
+    pub en_passant: Option<Square>,
+    pub halfmove_clock: u32,
 }
 
-#[derive(Debug)]
+/// A chess position along with the history needed for repetitions and taking moves back.
+#[derive(Debug, Clone)]
 pub struct Board {
-    pub squares: [[Square; 8]; 8],
-    pub last_move: Option<LastMove>,
-    pub w_king_pos: (usize, usize),
-    pub b_king_pos: (usize, usize),
-    pub w_in_check: bool,
-    pub b_in_check: bool,
+    /// One per kind of piece with both colors mixed, indexed by `piece as usize`.
+    pub pieces: [Bitboard; 6],
+    /// White then black, indexed by `color as usize`.
+    pub colors: [Bitboard; 2],
+    /// The same pieces by square, for display and asking what's on a square.
+    pub mailbox: [Option<(Piece, Color)>; 64],
+    pub side_to_move: Color,
+    /// The square a pawn skipped over with a double push on the last move.
+    pub en_passant: Option<Square>,
+    pub castling: CastlingRights,
+    /// Plies since the last capture or pawn move.
+    pub halfmove_clock: u32,
+    /// Starts at 1 and goes up after every black move.
+    pub fullmove_number: u32,
+    /// Zobrist key of the position, see `zobrist.rs`.
+    pub hash: u64,
+    /// The key of every position so far, the current one last.
+    pub position_history: Vec<u64>,
+    /// Every move played with `push_move`, so the game can be rewound.
+    pub move_stack: Vec<(Move, UndoInfo)>,
 }
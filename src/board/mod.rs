@@ -1,3 +1,14 @@
+//! The position itself and the rules of the game: placing and moving pieces, legal move generation,
+//! game results and perft for checking it all.
+
+#![allow(clippy::module_inception)]
+
+pub mod attacks;
+pub mod bitboard;
 pub mod board;
 pub mod placement;
-pub mod move_generation;
\ No newline at end of file
+pub mod move_generation;
+pub mod perft;
+pub mod square;
+pub mod status;
+pub mod zobrist;
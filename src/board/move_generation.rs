@@ -1,202 +1,145 @@
-use colored::Colorize;
-
 use crate::Board;
-use crate::board::board::Piece;
-use crate::utils::board::choose_promotion_piece;
-
-pub fn generate_valid_moves(board: &mut Board, color: bool) -> Vec<((usize, usize), (usize, usize))> {
-    let mut valid_moves = Vec::new();
+use crate::board::bitboard::{
+    bishop_attacks, bit, king_attacks, knight_attacks, pawn_attacks, queen_attacks, rook_attacks, squares, Bitboard,
+};
+use crate::board::board::{Color, Piece};
+use crate::board::square::Square;
+use crate::moves::Move;
+
+/// The legal moves for the side to move, only moves that don't leave our own king in check.
+pub fn generate_valid_moves(board: &Board) -> Vec<Move> {
+    let color = board.side_to_move;
+    let mut moves = gen_all_moves_for_color(board, color);
+    moves.extend(generate_castling_moves(board, color));
+
+    let mut scratch = board.clone(); // one copy to try every move on instead of one per move
+    moves.retain(|&mv| !leaves_king_in_check(&mut scratch, mv, color));
 
-    for i in 0..8 {
-        for j in 0..8 {
-            if let Some((piece, piece_color)) = board.squares[i][j].piece {
-                if piece_color == color {
-                    match piece {
-                        Piece::P => valid_moves.extend(generate_pawn_moves(board, (i, j), color)),
-                        
-                        Piece::B => valid_moves.extend(generate_bishop_moves(board, (i, j), color)),
-
-                        Piece::R => valid_moves.extend(generate_rook_moves(board, (i, j), color)),
+    moves
+}
 
-                        Piece::N => valid_moves.extend(generate_knight_moves(board, (i, j), color)),
+/// Whether playing `mv` would leave the king of `color` attacked. The board comes back unchanged.
+pub fn leaves_king_in_check(board: &mut Board, mv: Move, color: Color) -> bool {
+    let undo = board.make_move(mv);
+    let in_check = board.in_check(color);
+    board.unmake_move(mv, &undo);
 
-                        Piece::Q => valid_moves.extend(generate_queen_or_king_moves(board, (i, j), color)),
+    in_check
+}
 
-                        Piece::K => valid_moves.extend(generate_queen_or_king_moves(board, (i, j), color)),
-                    }
-                }
-            }
-        }
+fn generate_castling_moves(board: &Board, color: Color) -> Vec<Move> { // kept out of gen_all_moves_for_color since castling never attacks anything
+    let mut castling_moves = Vec::new();
+    let king = Square::new(if color == Color::White { 4 } else { 60 });
+    let (kingside, queenside) = if color == Color::White {
+        (board.castling.w_kingside, board.castling.w_queenside)
+    } else {
+        (board.castling.b_kingside, board.castling.b_queenside)
+    };
+
+    if board.piece_at(king) != Some((Piece::K, color)) || board.is_square_attacked(king, !color) { // no castling out of check
+        return castling_moves;
     }
 
-    valid_moves
-}
+    let occupied = board.occupied();
 
-fn generate_pawn_moves(board: &mut Board, from: (usize, usize), color: bool) -> Vec<((usize, usize), (usize, usize))> {
-    let mut valid_moves = Vec::new();
-    let direction = if color { -1 } else { 1 };
-    let row1 = (from.0 as isize + direction) as usize;
-    let row2 = (from.0 as isize + (direction + direction)) as usize;
+    // the king stays on its rank, so these are all on the board
+    let along = |files: isize| king.offset(files, 0).expect("castling squares are on the board");
 
-    if row1 < 8 && board.squares[row1][from.1].piece.is_none() {
-        valid_moves.push(((from.0, from.1), (row1, from.1)));
+    // the square the king passes over must be safe too, the landing square is checked like any other move
+    if kingside && board.piece_at(along(3)) == Some((Piece::R, color))
+        && occupied & (bit(along(1)) | bit(along(2))) == 0
+        && !board.is_square_attacked(along(1), !color) {
+        castling_moves.push(Move::new(king, along(2), Move::KING_CASTLE));
     }
 
-    if (color && from.0 == 1) || (!color && from.0 == 6) {
-        if row2 < 8 && board.squares[row1][from.1].piece.is_none() && board.squares[row2][from.1].piece.is_none() {
-            valid_moves.push(((from.0, from.1), (row2, from.1)));
-        }
+    if queenside && board.piece_at(along(-4)) == Some((Piece::R, color))
+        && occupied & (bit(along(-1)) | bit(along(-2)) | bit(along(-3))) == 0
+        && !board.is_square_attacked(along(-1), !color) {
+        castling_moves.push(Move::new(king, along(-2), Move::QUEEN_CASTLE));
     }
 
-    let new_col_left = (from.1 as isize - 1) as usize;
-    if row1 < 8 && new_col_left < 8 && board.squares[row1][new_col_left].piece.is_some() {
-        let (_, captured_color) = board.squares[row1][new_col_left].piece.unwrap();
-        if captured_color != color {
-            valid_moves.push(((from.0, from.1), (row1, new_col_left)));
-        }
-    }
+    castling_moves
+}
 
-    let new_col_right = (from.1 as isize + 1) as usize;
-    if row1 < 8 && new_col_right < 8 && board.squares[row1][new_col_right].piece.is_some() {
-        let (_, captured_color) = board.squares[row1][new_col_right].piece.unwrap();
-        if captured_color != color {
-            valid_moves.push(((from.0, from.1), (row1, new_col_right)));
-        }
-    }
+fn generate_pawn_moves(board: &Board, color: Color, valid_moves: &mut Vec<Move>) {
+    let empty = !board.occupied();
+    let them = board.colors[!color as usize];
 
-    if let Some(last_move) = board.last_move {
-        if last_move.piece == Piece::P && last_move.color != color {
-            if last_move.position.1 == from.1 {
-                if last_move.position.0 as isize + direction == row1 as isize {
-                    if new_col_left < 8 && board.squares[row1][new_col_left].piece.is_none() {
-                        valid_moves.push(((from.0, from.1), (row1, new_col_left)));
-                    }
-                    if new_col_right < 8 && board.squares[row1][new_col_right].piece.is_none() {
-                        valid_moves.push(((from.0, from.1), (row1, new_col_right)));
-                    }
+    for from in squares(board.pieces_of(Piece::P, color)) {
+        if let Some(one) = from.forward(color, 1).filter(|&one| empty & bit(one) != 0) {
+            add_pawn_move(from, one, false, valid_moves);
+
+            if from.relative_rank(color) == 1 {
+                let two = from.forward(color, 2).expect("the start rank is two away from the edge");
+                if empty & bit(two) != 0 {
+                    valid_moves.push(Move::new(from, two, Move::DOUBLE_PAWN_PUSH));
                 }
             }
         }
-    }
 
-    if (color && row1 == 0) || (!color && row1 == 7) {
-        valid_moves.push(((from.0, from.1), (row1, from.1)));
-    }
-
-    valid_moves
-}
-
-fn generate_queen_or_king_moves(board: &Board, from: (usize, usize), color: bool) -> Vec<((usize, usize), (usize, usize))> {
-    let mut valid_moves = Vec::new();
-
-    add_moves_in_direction(board, from, -1, 1, color, &mut valid_moves);
-    add_moves_in_direction(board, from, -1, -1, color, &mut valid_moves);
-    add_moves_in_direction(board, from, 1, 1, color, &mut valid_moves);
-    add_moves_in_direction(board, from, 1, -1, color, &mut valid_moves);
-    add_moves_in_direction(board, from, -1, 0, color, &mut valid_moves);
-    add_moves_in_direction(board, from, 1, 0, color, &mut valid_moves);
-    add_moves_in_direction(board, from, 0, 1, color, &mut valid_moves);
-    add_moves_in_direction(board, from, 0, -1, color, &mut valid_moves);
+        for to in squares(pawn_attacks(color, from) & them) {
+            add_pawn_move(from, to, true, valid_moves);
+        }
 
-    valid_moves
+        if let Some(target) = board.en_passant { // the square a pawn just skipped over with a double push
+            if pawn_attacks(color, from) & bit(target) != 0 {
+                valid_moves.push(Move::new(from, target, Move::EN_PASSANT));
+            }
+        }
+    }
 }
 
-fn generate_knight_moves(board: &Board, from: (usize, usize), color: bool) -> Vec<((usize, usize), (usize, usize))> {
-    let mut valid_moves = Vec::new();
-
-    add_moves_in_direction(board, from, -1, 2, color, &mut valid_moves);
-    add_moves_in_direction(board, from, 1, 2, color, &mut valid_moves);
-    add_moves_in_direction(board, from, 2, 1, color, &mut valid_moves);
-    add_moves_in_direction(board, from, 2, -1, color, &mut valid_moves);
-    add_moves_in_direction(board, from, -1, -2, color, &mut valid_moves);
-    add_moves_in_direction(board, from, 1, -2, color, &mut valid_moves);
-    add_moves_in_direction(board, from, -2, 1, color, &mut valid_moves);
-    add_moves_in_direction(board, from, -2, -1, color, &mut valid_moves);
-
-    valid_moves
+fn add_pawn_move(from: Square, to: Square, capture: bool, valid_moves: &mut Vec<Move>) { // one move per promotion piece on the last rank
+    if to.rank() == 0 || to.rank() == 7 {
+        for piece in [Piece::Q, Piece::R, Piece::B, Piece::N] {
+            valid_moves.push(Move::new(from, to, Move::promotion_flags(piece, capture)));
+        }
+    } else {
+        valid_moves.push(Move::new(from, to, if capture { Move::CAPTURE } else { Move::QUIET }));
+    }
 }
 
-fn generate_rook_moves(board: &Board, from: (usize, usize), color: bool) -> Vec<((usize, usize), (usize, usize))> {
-    let mut valid_moves = Vec::new();
-
-    add_moves_in_direction(board, from, -1, 0, color, &mut valid_moves);
-    add_moves_in_direction(board, from, 1, 0, color, &mut valid_moves);
-    add_moves_in_direction(board, from, 0, 1, color, &mut valid_moves);
-    add_moves_in_direction(board, from, 0, -1, color, &mut valid_moves);
-
-    valid_moves
+fn add_moves( // every target that isn't one of our own pieces, flagged as a capture if one of theirs is there
+    board: &Board,
+    from: Square,
+    targets: Bitboard,
+    color: Color,
+    valid_moves: &mut Vec<Move>
+) {
+    let them = board.colors[!color as usize];
 
+    for to in squares(targets & !board.colors[color as usize]) {
+        let flags = if them & bit(to) != 0 { Move::CAPTURE } else { Move::QUIET };
+        valid_moves.push(Move::new(from, to, flags));
+    }
 }
 
-fn generate_bishop_moves(board: &Board, from: (usize, usize), color: bool) -> Vec<((usize, usize), (usize, usize))> {
-    let mut valid_moves = Vec::new();
+/// The pseudo-legal moves for `color` other than castling, our king may be left in check.
+pub fn gen_all_moves_for_color(board: &Board, color: Color) -> Vec<Move> {
+    let mut moves = Vec::new();
+    let occupied = board.occupied();
 
-    add_moves_in_direction(board, from, -1, 1, color, &mut valid_moves);
-    add_moves_in_direction(board, from, -1, -1, color, &mut valid_moves);
-    add_moves_in_direction(board, from, 1, 1, color, &mut valid_moves);
-    add_moves_in_direction(board, from, 1, -1, color, &mut valid_moves);
+    generate_pawn_moves(board, color, &mut moves);
 
-    valid_moves
-}
-
-fn add_moves_in_direction( // function to add moves in a given direction
-    board: &Board,
-    from: (usize, usize),
-    row_delta: isize,
-    col_delta: isize,
-    color: bool,
-    valid_moves: &mut Vec<((usize, usize), (usize, usize))>
-) {
-    let (mut row, mut col) = (from.0 as isize, from.1 as isize);
-
-    loop {
-        row += row_delta;
-        col += col_delta;
+    for from in squares(board.pieces_of(Piece::N, color)) {
+        add_moves(board, from, knight_attacks(from), color, &mut moves);
+    }
 
-        if row < 0 || row >= 8 || col < 0 || col >= 8 {
-            break;
-        }
+    for from in squares(board.pieces_of(Piece::B, color)) {
+        add_moves(board, from, bishop_attacks(from, occupied), color, &mut moves);
+    }
 
-        let to = (row as usize, col as usize);
-        if let Some((_, piece_color)) = board.squares[to.0][to.1].piece {
-            if piece_color != color {
-                valid_moves.push((from, to));
-            }
-            break;
-        } else {
-            valid_moves.push((from, to));
-        }
+    for from in squares(board.pieces_of(Piece::R, color)) {
+        add_moves(board, from, rook_attacks(from, occupied), color, &mut moves);
     }
-}
 
-pub fn gen_all_moves_for_color(board: &mut Board, color: bool) -> Vec<((usize, usize), (usize, usize))> {
-    let mut moves = Vec::new();
+    for from in squares(board.pieces_of(Piece::Q, color)) {
+        add_moves(board, from, queen_attacks(from, occupied), color, &mut moves);
+    }
 
-    for row in 0..8 {
-        for col in 0..8 {
-            if let Some((piece, piece_color)) = board.squares[row][col].piece {
-                if piece_color == color {
-                    match piece {
-                        Piece::P => {
-                            moves.extend(generate_pawn_moves(board, (row, col), color));
-                        },
-                        Piece::N => {
-                            moves.extend(generate_knight_moves(board, (row, col), color));
-                        },
-                        Piece::B => {
-                            moves.extend(generate_bishop_moves(board, (row, col), color));
-                        },
-                        Piece::R => {
-                            moves.extend(generate_rook_moves(board, (row, col), color));
-                        },
-                        Piece::Q | Piece::K => {
-                            moves.extend(generate_queen_or_king_moves(board, (row, col), color));
-                        },
-                    }
-                }
-            }
-        }
+    for from in squares(board.pieces_of(Piece::K, color)) {
+        add_moves(board, from, king_attacks(from), color, &mut moves);
     }
 
     moves
-}
\ No newline at end of file
+}
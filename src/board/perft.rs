@@ -0,0 +1,39 @@
+use crate::Board;
+use crate::board::move_generation::generate_valid_moves;
+use crate::moves::Move;
+
+impl Board {
+    /// Counts the leaf nodes of the legal move tree, for checking the generator.
+    pub fn perft(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let moves = generate_valid_moves(self);
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+
+        let mut nodes = 0;
+        for mv in moves {
+            let undo = self.make_move(mv);
+            nodes += self.perft(depth - 1);
+            self.unmake_move(mv, &undo);
+        }
+
+        nodes
+    }
+
+    /// `perft` split up by the first move, to narrow down where a count goes wrong.
+    pub fn divide(&mut self, depth: u32) -> Vec<(Move, u64)> {
+        let mut counts = Vec::new();
+
+        for mv in generate_valid_moves(self) {
+            let undo = self.make_move(mv);
+            counts.push((mv, self.perft(depth.saturating_sub(1))));
+            self.unmake_move(mv, &undo);
+        }
+
+        counts
+    }
+}
@@ -0,0 +1,106 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::board::board::Color;
+use crate::moves::MoveError;
+
+/// One of the 64 squares, numbered 0 (a1) to 63 (h8) rank by rank. `Display` and `FromStr` use
+/// algebraic names like `e4`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Square(u8);
+
+impl Square {
+    /// The square with this index, 0 to 63. Panics on anything bigger, use `from_file_rank` or
+    /// `offset` when that can happen.
+    pub fn new(index: usize) -> Square {
+        assert!(index < 64, "square index {} is off the board", index);
+        Square(index as u8)
+    }
+
+    /// The square on `file` (0 is the a-file) and `rank` (0 is the first rank), `None` off the board.
+    pub fn from_file_rank(file: usize, rank: usize) -> Option<Square> {
+        if file < 8 && rank < 8 { Some(Square((rank * 8 + file) as u8)) } else { None }
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    /// 0 for the a-file up to 7 for the h-file.
+    pub fn file(self) -> usize {
+        self.index() % 8
+    }
+
+    /// 0 for the first rank up to 7 for the eighth.
+    pub fn rank(self) -> usize {
+        self.index() / 8
+    }
+
+    /// The square this many files right and ranks up from white's side, `None` if that's off the board.
+    pub fn offset(self, file_delta: isize, rank_delta: isize) -> Option<Square> {
+        let file = self.file().checked_add_signed(file_delta)?;
+        let rank = self.rank().checked_add_signed(rank_delta)?;
+
+        Square::from_file_rank(file, rank)
+    }
+
+    /// The square `ranks` ahead of this one as a pawn of `color` walks, `None` off the board.
+    pub fn forward(self, color: Color, ranks: isize) -> Option<Square> {
+        self.offset(0, if color == Color::White { ranks } else { -ranks })
+    }
+
+    /// The rank counted from `color`'s side of the board, so 0 is white's first rank and black's eighth.
+    pub fn relative_rank(self, color: Color) -> usize {
+        if color == Color::White { self.rank() } else { 7 - self.rank() }
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.file() as u8) as char, self.rank() + 1)
+    }
+}
+
+impl FromStr for Square {
+    type Err = MoveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = s.as_bytes();
+        if bytes.len() != 2 || !(b'a'..=b'h').contains(&bytes[0]) || !(b'1'..=b'8').contains(&bytes[1]) {
+            return Err(MoveError::InvalidSquare(s.to_string()));
+        }
+
+        Ok(Square::new((bytes[1] - b'1') as usize * 8 + (bytes[0] - b'a') as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for index in 0..64 {
+            let square = Square::new(index);
+            assert_eq!(square.to_string().parse::<Square>(), Ok(square));
+        }
+
+        assert_eq!("e4".parse::<Square>().map(|square| (square.file(), square.rank())), Ok((4, 3)));
+        assert!("i1".parse::<Square>().is_err());
+        assert!("a9".parse::<Square>().is_err());
+        assert!("e".parse::<Square>().is_err());
+    }
+
+    #[test]
+    fn offsets_stop_at_the_edge() {
+        let a1 = Square::new(0);
+        let h8 = Square::new(63);
+
+        assert_eq!(a1.offset(1, 2).map(|square| square.to_string()), Some("b3".to_string()));
+        assert_eq!(a1.offset(-1, 0), None);
+        assert_eq!(a1.offset(0, -1), None);
+        assert_eq!(h8.offset(1, 0), None);
+        assert_eq!(h8.forward(Color::White, 1), None);
+        assert_eq!(h8.forward(Color::Black, 2).map(|square| square.to_string()), Some("h6".to_string()));
+    }
+}
@@ -0,0 +1,224 @@
+use crate::Board;
+use crate::board::bitboard::Bitboard;
+use crate::board::board::{Color, Piece};
+use crate::board::move_generation::generate_valid_moves;
+
+/// Whether the game is still going and how it ended if it isn't.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameStatus {
+    Ongoing,
+    Checkmate(Color), // holds the color that delivered mate
+    Stalemate,
+    Draw(DrawReason),
+}
+
+/// The ways a game can be drawn other than stalemate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DrawReason {
+    FiftyMoveRule, // claimable
+    ThreefoldRepetition, // claimable
+    SeventyFiveMoveRule,
+    FivefoldRepetition,
+    InsufficientMaterial,
+}
+
+impl Board {
+    /// Whether the game is over. Only automatic draws end the game here, claimable ones are up to the player.
+    pub fn status(&self) -> GameStatus {
+        let color = self.side_to_move;
+        if generate_valid_moves(self).is_empty() {
+            return if self.in_check(color) {
+                GameStatus::Checkmate(!color)
+            } else {
+                GameStatus::Stalemate
+            };
+        }
+
+        if self.halfmove_clock >= 150 {
+            return GameStatus::Draw(DrawReason::SeventyFiveMoveRule);
+        }
+
+        if self.repetition_count() >= 5 {
+            return GameStatus::Draw(DrawReason::FivefoldRepetition);
+        }
+
+        if self.is_insufficient_material() {
+            return GameStatus::Draw(DrawReason::InsufficientMaterial);
+        }
+
+        GameStatus::Ongoing
+    }
+
+    /// A draw the side to move may claim but that doesn't happen on its own, see `status` for the rest.
+    pub fn claimable_draw(&self) -> Option<DrawReason> {
+        if self.repetition_count() >= 3 {
+            return Some(DrawReason::ThreefoldRepetition);
+        }
+
+        if self.halfmove_clock >= 100 {
+            return Some(DrawReason::FiftyMoveRule);
+        }
+
+        None
+    }
+
+    /// How many times the current position has appeared, itself included.
+    pub fn repetition_count(&self) -> usize {
+        let current = match self.position_history.last() {
+            Some(hash) => hash,
+            None => return 1,
+        };
+
+        // a capture or pawn move can't be undone, so nothing before the last one can repeat
+        self.position_history
+            .iter()
+            .rev()
+            .take(self.halfmove_clock as usize + 1)
+            .filter(|&hash| hash == current)
+            .count()
+    }
+
+    /// Neither side can ever mate, whatever happens.
+    pub fn is_insufficient_material(&self) -> bool {
+        const LIGHT_SQUARES: Bitboard = 0x55aa_55aa_55aa_55aa;
+
+        if self.pieces[Piece::P as usize] | self.pieces[Piece::R as usize] | self.pieces[Piece::Q as usize] != 0 {
+            return false;
+        }
+
+        let bishops = self.pieces[Piece::B as usize];
+        let minors = bishops | self.pieces[Piece::N as usize];
+
+        if minors.count_ones() <= 1 { // K vs K, K+B vs K, K+N vs K
+            return true;
+        }
+
+        // any number of bishops all on the same color of square can't cover the squares a mate needs
+        minors == bishops && (bishops & LIGHT_SQUARES == 0 || bishops & !LIGHT_SQUARES == 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::san::parse_san;
+
+    fn play(board: &mut Board, moves: &[&str]) {
+        for san in moves {
+            let mv = parse_san(board, san).unwrap();
+            board.push_move(mv);
+        }
+    }
+
+    #[test]
+    fn checkmate_names_the_winner() {
+        let mut board = Board::new();
+        play(&mut board, &["f3", "e5", "g4", "Qh4"]);
+        assert_eq!(board.status(), GameStatus::Checkmate(Color::Black));
+
+        let mut board = Board::from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap();
+        assert_eq!(board.status(), GameStatus::Ongoing);
+        play(&mut board, &["Ra8"]);
+        assert_eq!(board.status(), GameStatus::Checkmate(Color::White));
+    }
+
+    #[test]
+    fn no_moves_without_check_is_stalemate() {
+        let board = Board::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(board.status(), GameStatus::Stalemate);
+
+        // the same squares covered but with black in check is mate
+        let board = Board::from_fen("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(board.status(), GameStatus::Checkmate(Color::White));
+    }
+
+    #[test]
+    fn fifty_moves_can_be_claimed_and_seventy_five_end_the_game() {
+        let mut board = Board::from_fen("r3k3/8/8/8/8/8/8/R3K3 w - - 99 80").unwrap();
+        assert_eq!(board.claimable_draw(), None);
+
+        play(&mut board, &["Kd1"]);
+        assert_eq!(board.claimable_draw(), Some(DrawReason::FiftyMoveRule));
+        assert_eq!(board.status(), GameStatus::Ongoing);
+
+        let mut board = Board::from_fen("r3k3/8/8/8/8/8/8/R3K3 w - - 149 80").unwrap();
+        assert_eq!(board.status(), GameStatus::Ongoing);
+        play(&mut board, &["Kd1"]);
+        assert_eq!(board.status(), GameStatus::Draw(DrawReason::SeventyFiveMoveRule));
+    }
+
+    #[test]
+    fn repetitions_can_be_claimed_at_three_and_end_the_game_at_five() {
+        let mut board = Board::new();
+        let shuffle = ["Nf3", "Nf6", "Ng1", "Ng8"];
+
+        play(&mut board, &shuffle);
+        assert_eq!(board.repetition_count(), 2);
+        assert_eq!(board.claimable_draw(), None);
+
+        play(&mut board, &shuffle);
+        assert_eq!(board.repetition_count(), 3);
+        assert_eq!(board.claimable_draw(), Some(DrawReason::ThreefoldRepetition));
+        assert_eq!(board.status(), GameStatus::Ongoing);
+
+        play(&mut board, &shuffle);
+        assert_eq!(board.status(), GameStatus::Ongoing);
+        play(&mut board, &shuffle);
+        assert_eq!(board.repetition_count(), 5);
+        assert_eq!(board.status(), GameStatus::Draw(DrawReason::FivefoldRepetition));
+    }
+
+    #[test]
+    fn lost_castling_rights_make_a_different_position() {
+        let mut board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let shuffle = ["Ra2", "Ra7", "Ra1", "Ra8"];
+
+        // the pieces are back where they started but neither side can castle queenside any more
+        play(&mut board, &shuffle);
+        assert_eq!(board.repetition_count(), 1);
+
+        play(&mut board, &shuffle);
+        play(&mut board, &shuffle);
+        assert_eq!(board.repetition_count(), 3);
+    }
+
+    #[test]
+    fn a_capturable_en_passant_square_makes_a_different_position() {
+        let mut board = Board::from_fen("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1").unwrap();
+        let shuffle = ["Ke7", "Ke2", "Ke8", "Ke1"];
+
+        // black could take on e3 right after the double push, but not once the kings have moved
+        play(&mut board, &["e4"]);
+        play(&mut board, &shuffle);
+        assert_eq!(board.repetition_count(), 1);
+
+        play(&mut board, &shuffle);
+        assert_eq!(board.repetition_count(), 2);
+    }
+
+    #[test]
+    fn bare_kings_and_lone_minors_cannot_mate() {
+        let drawn = [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1", // K vs K
+            "4k3/8/8/8/8/8/8/4KN2 w - - 0 1", // K+N vs K
+            "4k3/8/8/8/8/8/8/4KB2 w - - 0 1", // K+B vs K
+            "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1", // bishops on the same color
+        ];
+        for fen in drawn {
+            let board = Board::from_fen(fen).unwrap();
+            assert!(board.is_insufficient_material(), "{}", fen);
+            assert_eq!(board.status(), GameStatus::Draw(DrawReason::InsufficientMaterial), "{}", fen);
+        }
+
+        let playable = [
+            "2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1", // bishops on opposite colors
+            "4k3/8/8/8/8/8/8/3NKN2 w - - 0 1",
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+        ];
+        for fen in playable {
+            let board = Board::from_fen(fen).unwrap();
+            assert!(!board.is_insufficient_material(), "{}", fen);
+            assert_eq!(board.status(), GameStatus::Ongoing, "{}", fen);
+        }
+    }
+}
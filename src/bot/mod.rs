@@ -1 +0,0 @@
-pub mod easy;
\ No newline at end of file
@@ -0,0 +1,14 @@
+use rand::thread_rng;
+use rand::seq::SliceRandom;
+
+use crate::Board;
+use crate::board::move_generation::generate_valid_moves;
+use crate::moves::Move;
+
+/// A random legal move for the side to move, `None` when the game is already over.
+pub fn choose_bot_move(board: &Board) -> Option<Move> {
+    let valid_moves = generate_valid_moves(board);
+
+    let mut rng = thread_rng();
+    valid_moves.choose(&mut rng).copied()
+}
@@ -0,0 +1,4 @@
+//! Picking moves for the computer side.
+
+pub mod easy;
+pub mod search;
@@ -0,0 +1,302 @@
+use std::time::{Duration, Instant};
+
+use crate::{Board, Color, Piece};
+use crate::board::bitboard::squares;
+use crate::board::move_generation::generate_valid_moves;
+use crate::moves::Move;
+
+/// Score of mating right now, mates further away score a little less so the quickest one is preferred.
+pub const MATE: i32 = 100_000;
+const INFINITY: i32 = MATE + 1;
+const MAX_DEPTH: i32 = 64;
+const DEFAULT_DEPTH: u32 = 4;
+
+// indexed by `piece as usize`, the king never gets captured so it's worth nothing here
+const PIECE_VALUES: [i32; 6] = [0, 900, 500, 330, 320, 100];
+
+/// When `search` has to stop, whichever limit comes first. With neither it goes on until it finds a
+/// mate, which in most positions is never. The default stops after 4 plies.
+#[derive(Debug, Clone, Copy)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub time: Option<Duration>,
+}
+
+impl Default for SearchLimits {
+    fn default() -> Self {
+        SearchLimits { depth: Some(DEFAULT_DEPTH), time: None }
+    }
+}
+
+/// What `search` came up with, from the last depth it finished.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchResult {
+    /// `None` only when there's no legal move.
+    pub best_move: Option<Move>,
+    /// In centipawns for the side to move, or `MATE` less the plies to mate.
+    pub score: i32,
+    pub depth: u32,
+    pub nodes: u64,
+}
+
+impl SearchResult {
+    /// Moves until mate, positive when the side to move mates and negative when it gets mated.
+    pub fn mate_in(&self) -> Option<i32> {
+        let plies = MATE - self.score.abs();
+        if plies > MAX_DEPTH * 2 {
+            return None;
+        }
+
+        let moves = (plies + 1) / 2;
+        Some(if self.score > 0 { moves } else { -moves })
+    }
+}
+
+/// Looks for the best move with an iterative deepening alpha-beta search over material and a
+/// little piece placement, with captures played out at the end of each line.
+pub fn search(board: &Board, limits: SearchLimits) -> SearchResult {
+    let mut searcher = Searcher {
+        board: board.clone(),
+        nodes: 0,
+        deadline: limits.time.map(|time| Instant::now() + time),
+        stopped: false,
+    };
+    searcher.board.move_stack.clear(); // never popped in here, and it'd get copied at every node
+
+    let mut moves = generate_valid_moves(board);
+    if moves.is_empty() {
+        let score = if board.in_check(board.side_to_move) { -MATE } else { 0 };
+        return SearchResult { best_move: None, score, depth: 0, nodes: 0 };
+    }
+    order_moves(board, &mut moves);
+
+    // something to play even if the first depth doesn't finish in time
+    let mut result = SearchResult { best_move: Some(moves[0]), score: 0, depth: 0, nodes: 0 };
+    let max_depth = limits.depth.map_or(MAX_DEPTH, |depth| (depth as i32).clamp(1, MAX_DEPTH));
+
+    for depth in 1..=max_depth {
+        let mut alpha = -INFINITY;
+        let mut best = moves[0];
+
+        for &mv in &moves {
+            let undo = searcher.board.make_move(mv);
+            let score = -searcher.negamax(depth - 1, 1, -INFINITY, -alpha);
+            searcher.board.unmake_move(mv, &undo);
+
+            if searcher.stopped {
+                break;
+            }
+            if score > alpha {
+                alpha = score;
+                best = mv;
+            }
+        }
+
+        if searcher.stopped { // a depth that didn't finish can't be trusted
+            break;
+        }
+
+        // the best move so far goes first next time, it's the most likely to still be best
+        moves.retain(|&mv| mv != best);
+        moves.insert(0, best);
+        result = SearchResult { best_move: Some(best), score: alpha, depth: depth as u32, nodes: searcher.nodes };
+
+        if result.mate_in().is_some() { // deeper won't find a quicker mate
+            break;
+        }
+    }
+
+    result.nodes = searcher.nodes;
+    result
+}
+
+struct Searcher {
+    board: Board,
+    nodes: u64,
+    deadline: Option<Instant>,
+    stopped: bool,
+}
+
+impl Searcher {
+    fn negamax(&mut self, mut depth: i32, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+        if self.out_of_time() {
+            return 0;
+        }
+
+        // repeating a position is as good as a draw, the other side can always repeat again
+        if self.board.halfmove_clock >= 100 || self.board.repetition_count() >= 2 {
+            return 0;
+        }
+
+        let color = self.board.side_to_move;
+        let in_check = self.board.in_check(color);
+        if in_check { // look a move further so mates at the end of a line aren't missed
+            depth += 1;
+        }
+
+        if depth <= 0 || ply >= MAX_DEPTH * 2 {
+            return self.quiesce(alpha, beta);
+        }
+
+        let mut moves = generate_valid_moves(&self.board);
+        if moves.is_empty() {
+            return if in_check { -(MATE - ply) } else { 0 };
+        }
+        order_moves(&self.board, &mut moves);
+
+        for mv in moves {
+            let undo = self.board.make_move(mv);
+            let score = -self.negamax(depth - 1, ply + 1, -beta, -alpha);
+            self.board.unmake_move(mv, &undo);
+
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                return beta;
+            }
+            alpha = alpha.max(score);
+        }
+
+        alpha
+    }
+
+    fn quiesce(&mut self, mut alpha: i32, beta: i32) -> i32 { // only captures, until the position is quiet
+        if self.out_of_time() {
+            return 0;
+        }
+
+        let stand_pat = evaluate(&self.board);
+        if stand_pat >= beta {
+            return beta;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut moves = generate_valid_moves(&self.board);
+        moves.retain(|mv| mv.is_capture() || mv.promotion().is_some());
+        order_moves(&self.board, &mut moves);
+
+        for mv in moves {
+            let undo = self.board.make_move(mv);
+            let score = -self.quiesce(-beta, -alpha);
+            self.board.unmake_move(mv, &undo);
+
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                return beta;
+            }
+            alpha = alpha.max(score);
+        }
+
+        alpha
+    }
+
+    fn out_of_time(&mut self) -> bool { // only looks at the clock every so often, it isn't free
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(1024) && self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            self.stopped = true;
+        }
+        self.stopped
+    }
+}
+
+/// The position from the side to move's point of view, in centipawns.
+pub fn evaluate(board: &Board) -> i32 {
+    let score = |color: Color| -> i32 {
+        let mut total = 0;
+        for piece in [Piece::Q, Piece::R, Piece::B, Piece::N, Piece::P] {
+            for square in squares(board.pieces_of(piece, color)) {
+                // how far from the four middle squares, 0 on them and 3 in a corner
+                let file_distance = if square.file() < 4 { 3 - square.file() } else { square.file() - 4 };
+                let rank_distance = if square.rank() < 4 { 3 - square.rank() } else { square.rank() - 4 };
+                let centrality = 3 - file_distance.max(rank_distance) as i32;
+
+                total += PIECE_VALUES[piece as usize] + match piece {
+                    Piece::N => centrality * 10,
+                    Piece::B => centrality * 5,
+                    Piece::P => (square.relative_rank(color) as i32 - 1) * 5,
+                    _ => 0,
+                };
+            }
+        }
+        total
+    };
+
+    let color = board.side_to_move;
+    score(color) - score(!color)
+}
+
+// biggest captures by the smallest pieces first, then promotions, then the rest
+fn order_moves(board: &Board, moves: &mut [Move]) {
+    moves.sort_by_key(|&mv| {
+        let attacker = board.piece_at(mv.from()).map_or(0, |(piece, _)| PIECE_VALUES[piece as usize]);
+        let victim = match board.piece_at(mv.to()) {
+            Some((piece, _)) => PIECE_VALUES[piece as usize],
+            None if mv.is_en_passant() => PIECE_VALUES[Piece::P as usize],
+            None => 0,
+        };
+        let promotion = mv.promotion().map_or(0, |piece| PIECE_VALUES[piece as usize]);
+
+        if victim > 0 {
+            -(victim * 10 - attacker / 10) - promotion
+        } else {
+            -promotion
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::san::to_san;
+
+    fn best(fen: &str, depth: u32) -> (String, SearchResult) {
+        let board = Board::from_fen(fen).unwrap();
+        let result = search(&board, SearchLimits { depth: Some(depth), time: None });
+        (to_san(&board, result.best_move.unwrap()), result)
+    }
+
+    #[test]
+    fn finds_mates() {
+        let (san, result) = best("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1", 3);
+        assert_eq!(san, "Ra8#");
+        assert_eq!(result.mate_in(), Some(1));
+
+        let (san, result) = best("kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1", 4);
+        assert_eq!(san, "Ra6");
+        assert_eq!(result.mate_in(), Some(2));
+    }
+
+    #[test]
+    fn takes_hanging_pieces() {
+        let (san, result) = best("4k3/8/8/3q4/8/8/3R4/3K4 w - - 0 1", 2);
+        assert_eq!(san, "Rxd5");
+        assert!(result.score > 400);
+    }
+
+    #[test]
+    fn stops_in_time() {
+        let board = Board::new();
+        let started = Instant::now();
+        let result = search(&board, SearchLimits { depth: None, time: Some(Duration::from_millis(200)) });
+
+        assert!(result.best_move.is_some());
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn the_default_has_a_limit() {
+        let result = search(&Board::new(), SearchLimits::default());
+        assert!(result.best_move.is_some());
+        assert_eq!(result.depth, DEFAULT_DEPTH);
+    }
+
+    #[test]
+    fn knows_when_the_game_is_over() {
+        let result = search(&Board::from_fen("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1").unwrap(), SearchLimits::default());
+        assert_eq!(result.best_move, None);
+        assert_eq!(result.mate_in(), Some(0));
+    }
+}
@@ -0,0 +1,551 @@
+//! A game as a tree of moves, for analysis: the mainline, sidelines off any move, and the comments,
+//! annotation glyphs and clock times that go with each move.
+
+use std::time::Duration;
+
+use crate::{Board, Move, MoveError};
+use crate::board::placement::find_legal_move;
+use crate::notation::fen::START_FEN;
+use crate::notation::pgn::{from_pgn, move_number, tag, wrap_movetext, PgnError, PgnMove, PgnTags};
+use crate::notation::san::{parse_san, to_san};
+
+/// Which node of a `Game` something is, only meaningful for the game it came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+/// A position in the game tree along with the move that led to it.
+#[derive(Debug, Clone, Default)]
+pub struct Node {
+    mv: Option<Move>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    /// Comments before the move, only written at the start of a game or variation.
+    pub comments_before: Vec<String>,
+    /// Comments after the move. The root's are about the starting position and are written before
+    /// the first move.
+    pub comments: Vec<String>,
+    /// Numeric annotation glyphs, 1 is `!`, 2 is `?` and so on.
+    pub nags: Vec<u8>,
+    /// Time left on the mover's clock, written as `[%clk h:mm:ss]` inside a comment.
+    pub clock: Option<Duration>,
+}
+
+impl Node {
+    /// The move that led here, `None` for the starting position.
+    pub fn mv(&self) -> Option<Move> {
+        self.mv
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    /// The moves played from here, the first one is the main continuation and the rest are variations.
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    fn has_comments(&self) -> bool { // the clock gets written as a comment too
+        !self.comments.is_empty() || self.clock.is_some()
+    }
+}
+
+/// A game with its starting position, tags and a tree of moves, and a cursor that's walked around
+/// the tree. The position at the cursor is kept in a `Board`.
+#[derive(Debug, Clone)]
+pub struct Game {
+    /// Every tag in the order they're written, the Seven Tag Roster first.
+    pub tags: Vec<(String, String)>,
+    start: Board,
+    nodes: Vec<Node>, // the root, the starting position, is always first
+    current: NodeId,
+    board: Board,
+}
+
+const ROOT: NodeId = NodeId(0);
+
+impl Game {
+    /// A game from the usual starting position with no moves yet.
+    pub fn new() -> Self {
+        Game::from_position(Board::new())
+    }
+
+    /// A game starting from `board`. Moves on its move stack become the mainline, and the game is
+    /// left at the end of them.
+    pub fn from_position(board: Board) -> Self {
+        let mut start = board.clone();
+        while start.pop_move().is_some() {}
+
+        let mut game = Game {
+            tags: PgnTags::default().roster().into_iter().map(|(name, value)| (name.to_string(), value.to_string())).collect(),
+            board: start.clone(),
+            start,
+            nodes: vec![Node::default()],
+            current: ROOT,
+        };
+        for (mv, _) in &board.move_stack {
+            game.current = game.add_child(game.current, *mv);
+        }
+        game.board = board;
+        game
+    }
+
+    /// Reads the first game of some PGN, variations and annotations included. The game is left at
+    /// the starting position. A variation with comments but no moves can't be a line in the tree, so
+    /// its comments go to the move it was an alternative to.
+    pub fn from_pgn(text: &str) -> Result<Game, PgnError> {
+        let pgn = from_pgn(text)?;
+
+        let mut game = Game::from_position(pgn.start.clone());
+        for (name, value) in pgn.tags {
+            if name != "SetUp" && name != "FEN" { // worked out from the starting position when written
+                game.set_tag(&name, &value);
+            }
+        }
+        game.set_tag("Result", &pgn.result);
+        game.add_line(ROOT, &pgn.moves);
+        game.nodes[ROOT.0].comments = pgn.comments;
+
+        Ok(game)
+    }
+
+    /// The value of a tag, if the game has it.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str())
+    }
+
+    /// Changes a tag, adding it after the others if the game doesn't have it yet.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old)) => *old = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// The position at the cursor.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn start(&self) -> &Board {
+        &self.start
+    }
+
+    pub fn root(&self) -> NodeId {
+        ROOT
+    }
+
+    pub fn current(&self) -> NodeId {
+        self.current
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    /// For annotating a node, the moves themselves can only be changed through the `Game`.
+    pub fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        &mut self.nodes[id.0]
+    }
+
+    /// Plays a move at the cursor and moves onto it. A move that was already there is reused,
+    /// otherwise it's added after the existing ones, as the main continuation if it's the first. Its
+    /// flags don't matter, a move from `Move::from_str` works too.
+    pub fn play(&mut self, mv: Move) -> Result<NodeId, MoveError> {
+        let mv = find_legal_move(&self.board, mv)?; // flags filled in, so it matches the moves already there
+        self.board.push_move(mv);
+
+        self.current = match self.node(self.current).children.iter().find(|&&child| self.node(child).mv == Some(mv)) {
+            Some(&child) => child,
+            None => self.add_child(self.current, mv),
+        };
+        Ok(self.current)
+    }
+
+    /// `play` with the move in SAN.
+    pub fn play_san(&mut self, san: &str) -> Result<NodeId, MoveError> {
+        let mv = parse_san(&self.board, san)?;
+        self.play(mv)
+    }
+
+    /// Steps to the main continuation, false at the end of a line.
+    pub fn forward(&mut self) -> bool {
+        match self.node(self.current).children.first() {
+            Some(&child) => {
+                self.step_into(child);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Steps back a move, false at the start of the game.
+    pub fn back(&mut self) -> bool {
+        match self.node(self.current).parent {
+            Some(parent) => {
+                self.board.pop_move();
+                self.current = parent;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// The alternatives to the main continuation from the cursor.
+    pub fn variations(&self) -> &[NodeId] {
+        self.node(self.current).children.get(1..).unwrap_or_default()
+    }
+
+    /// Steps into the `index`th variation at the cursor, the first move after it, false if there's
+    /// no such variation.
+    pub fn enter_variation(&mut self, index: usize) -> bool {
+        match self.variations().get(index) {
+            Some(&child) => {
+                self.step_into(child);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Moves the variation the cursor is in up a level, making it the main continuation where it
+    /// branches off. The line it replaces becomes the first variation and the rest keep their order.
+    /// Doing it enough times makes it the mainline. False if the cursor is already on the mainline.
+    pub fn promote_variation(&mut self) -> bool {
+        let mut id = self.current;
+        while let Some(parent) = self.node(id).parent {
+            let siblings = &mut self.nodes[parent.0].children;
+            if let Some(index) = siblings.iter().position(|&child| child == id).filter(|&index| index > 0) {
+                let id = siblings.remove(index);
+                siblings.insert(0, id);
+                return true;
+            }
+            id = parent;
+        }
+        false
+    }
+
+    /// Whether a node is on the game's mainline.
+    pub fn is_mainline(&self, mut id: NodeId) -> bool {
+        while let Some(parent) = self.node(id).parent {
+            if self.node(parent).children.first() != Some(&id) {
+                return false;
+            }
+            id = parent;
+        }
+        true
+    }
+
+    /// Moves the cursor anywhere in the tree.
+    pub fn go_to(&mut self, id: NodeId) {
+        let mut path = Vec::new();
+        let mut at = id;
+        while let Some(parent) = self.node(at).parent {
+            path.push(self.node(at).mv.expect("only the root has no move"));
+            at = parent;
+        }
+
+        self.board = self.start.clone();
+        for mv in path.into_iter().rev() {
+            self.board.push_move(mv);
+        }
+        self.current = id;
+    }
+
+    /// Writes the whole tree as PGN, with the Seven Tag Roster first and `SetUp` and `FEN` tags when
+    /// the game doesn't start from the usual position.
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::new();
+
+        let defaults = PgnTags::default();
+        let roster = defaults.roster();
+        for (name, missing) in roster {
+            pgn.push_str(&tag(name, self.tag(name).unwrap_or(missing)));
+        }
+
+        let start_fen = self.start.to_fen();
+        if start_fen != START_FEN {
+            pgn.push_str(&tag("SetUp", "1"));
+            pgn.push_str(&tag("FEN", &start_fen));
+        }
+
+        for (name, value) in &self.tags {
+            if !roster.iter().any(|&(roster_name, _)| roster_name == name) {
+                pgn.push_str(&tag(name, value));
+            }
+        }
+        pgn.push('\n');
+
+        let mut tokens = Vec::new();
+        for comment in &self.node(ROOT).comments {
+            write_comment(comment, &mut tokens);
+        }
+        self.write_continuation(ROOT, &mut self.start.clone(), true, &mut tokens);
+        tokens.push(self.tag("Result").unwrap_or("*").to_string());
+
+        pgn.push_str(&wrap_movetext(&tokens));
+        pgn
+    }
+
+    fn step_into(&mut self, child: NodeId) {
+        self.board.push_move(self.node(child).mv.expect("only the root has no move"));
+        self.current = child;
+    }
+
+    fn add_child(&mut self, parent: NodeId, mv: Move) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(Node { mv: Some(mv), parent: Some(parent), ..Node::default() });
+        self.nodes[parent.0].children.push(id);
+        id
+    }
+
+    fn add_line(&mut self, parent: NodeId, line: &[PgnMove]) {
+        let mut at = parent;
+        for pgn_move in line {
+            let id = self.add_child(at, pgn_move.mv);
+
+            let node = &mut self.nodes[id.0];
+            node.comments_before = pgn_move.comments_before.clone();
+            node.nags = pgn_move.nags.clone();
+            for comment in &pgn_move.comments {
+                let (clock, comment) = take_clock(comment);
+                node.clock = node.clock.or(clock);
+                if !comment.is_empty() {
+                    node.comments.push(comment);
+                }
+            }
+
+            for variation in &pgn_move.variations {
+                self.add_line(at, &variation.moves);
+                self.nodes[id.0].comments.extend(variation.comments.iter().cloned());
+            }
+            at = id;
+        }
+    }
+
+    // the moves after `id`, where `board` is the position at `id`
+    fn write_continuation(&self, id: NodeId, board: &mut Board, mut needs_number: bool, tokens: &mut Vec<String>) {
+        let children = &self.node(id).children;
+        let Some(&main) = children.first() else {
+            return;
+        };
+
+        self.write_move(main, board, needs_number, tokens);
+        for &variation in &children[1..] {
+            tokens.push("(".to_string());
+            self.write_move(variation, board, true, tokens);
+            board.push_move(self.node(variation).mv.expect("only the root has no move"));
+            self.write_continuation(variation, board, self.node(variation).has_comments(), tokens);
+            board.pop_move();
+            tokens.push(")".to_string());
+        }
+
+        // black's reply needs its number again if anything got in between
+        needs_number = children.len() > 1 || self.node(main).has_comments();
+        board.push_move(self.node(main).mv.expect("only the root has no move"));
+        self.write_continuation(main, board, needs_number, tokens);
+        board.pop_move();
+    }
+
+    fn write_move(&self, id: NodeId, board: &Board, needs_number: bool, tokens: &mut Vec<String>) {
+        let node = self.node(id);
+        let mv = node.mv.expect("only the root has no move");
+
+        for comment in &node.comments_before {
+            write_comment(comment, tokens);
+        }
+        tokens.extend(move_number(board, needs_number || !node.comments_before.is_empty()));
+        tokens.push(to_san(board, mv));
+        tokens.extend(node.nags.iter().map(|nag| format!("${}", nag)));
+
+        let mut comments = node.comments.clone();
+        if let Some(clock) = node.clock {
+            match comments.first_mut() {
+                Some(first) => *first = format!("{} {}", format_clock(clock), first),
+                None => comments.push(format_clock(clock)),
+            }
+        }
+        for comment in &comments {
+            write_comment(comment, tokens);
+        }
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn write_comment(comment: &str, tokens: &mut Vec<String>) { // a word at a time so long comments wrap
+    let words: Vec<&str> = comment.split_whitespace().collect();
+    match words.as_slice() {
+        [] => tokens.push("{}".to_string()),
+        [word] => tokens.push(format!("{{{}}}", word)),
+        [first, middle @ .., last] => {
+            tokens.push(format!("{{{}", first));
+            tokens.extend(middle.iter().map(|word| word.to_string()));
+            tokens.push(format!("{}}}", last));
+        },
+    }
+}
+
+// pulls "[%clk 1:02:03]" out of a comment, what's left of the comment comes back too
+fn take_clock(comment: &str) -> (Option<Duration>, String) {
+    let Some(start) = comment.find("[%clk") else {
+        return (None, comment.to_string());
+    };
+    let Some(length) = comment[start..].find(']') else {
+        return (None, comment.to_string());
+    };
+
+    let Some(clock) = parse_clock(comment[start + "[%clk".len()..start + length].trim()) else {
+        return (None, comment.to_string());
+    };
+    let rest = format!("{} {}", &comment[..start], &comment[start + length + 1..]);
+    (Some(clock), rest.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+fn parse_clock(text: &str) -> Option<Duration> { // h:mm:ss, the seconds can have a fraction
+    let mut parts = text.split(':');
+    let hours: u64 = parts.next()?.parse().ok()?;
+    let minutes: u64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() || !(0.0..60.0).contains(&seconds) || minutes >= 60 {
+        return None;
+    }
+
+    Some(Duration::from_secs(hours * 3600 + minutes * 60) + Duration::from_secs_f64(seconds))
+}
+
+fn format_clock(clock: Duration) -> String {
+    let seconds = clock.as_secs();
+    let mut text = format!("[%clk {}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if clock.subsec_millis() != 0 {
+        text.push_str(format!(".{:03}", clock.subsec_millis()).trim_end_matches('0'));
+    }
+    text.push(']');
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walks_the_tree() {
+        let mut game = Game::new();
+        for san in ["e4", "e5", "Nf3"] {
+            game.play_san(san).unwrap();
+        }
+        game.back();
+        game.back();
+        let sicilian = game.play_san("c5").unwrap();
+        game.play_san("Nf3").unwrap();
+
+        game.go_to(game.root());
+        assert!(game.forward());
+        assert_eq!(game.variations().len(), 1);
+
+        assert!(game.enter_variation(0));
+        assert_eq!(game.current(), sicilian);
+        assert!(!game.is_mainline(sicilian));
+        assert!(game.forward());
+        assert_eq!(game.board().to_fen(), "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+
+        // playing a move that's already there follows it instead of adding another
+        game.go_to(game.root());
+        game.play_san("e4").unwrap();
+        game.play_san("c5").unwrap();
+        assert_eq!(game.current(), sicilian);
+
+        assert!(game.promote_variation());
+        assert!(game.is_mainline(sicilian));
+        assert!(!game.promote_variation());
+
+        game.go_to(game.root());
+        while game.forward() {}
+        assert_eq!(game.board().move_stack.len(), 3);
+        assert!(!game.forward());
+    }
+
+    #[test]
+    fn promoting_keeps_the_other_variations_in_order() {
+        let mut game = Game::from_pgn("1. e4 (1. d4) (1. c4) (1. Nf3) *").unwrap();
+        assert!(game.enter_variation(2));
+        assert!(game.promote_variation());
+
+        assert_eq!(game.to_pgn().lines().last(), Some("1. Nf3 (1. e4) (1. d4) (1. c4) *"));
+    }
+
+    #[test]
+    fn parsed_moves_match_the_ones_in_the_tree() {
+        let mut game = Game::new();
+        let e4 = game.play_san("e4").unwrap();
+        game.back();
+
+        assert_eq!(game.play("e2e4".parse().unwrap()), Ok(e4));
+        assert_eq!(game.node(game.root()).children().len(), 1);
+    }
+
+    #[test]
+    fn refuses_illegal_moves() {
+        let mut game = Game::new();
+        assert!(game.play_san("e5").is_err());
+        assert_eq!(game.current(), game.root());
+        assert!(!game.back());
+    }
+
+    #[test]
+    fn round_trips_pgn() {
+        let pgn = concat!(
+            "[Event \"Study\"]\n",
+            "[Site \"?\"]\n",
+            "[Date \"2024.01.02\"]\n",
+            "[Round \"?\"]\n",
+            "[White \"A\"]\n",
+            "[Black \"B\"]\n",
+            "[Result \"*\"]\n",
+            "[Annotator \"C\"]\n",
+            "\n",
+            "{Opening} 1. e4 {[%clk 0:05:00] Best by test} 1... e5 (1... c5 $1 2. Nf3\n",
+            "(2. c3) 2... d6) 2. Nf3 $5 {[%clk 0:04:58.5]} 2... Nc6 *\n",
+        );
+
+        let game = Game::from_pgn(pgn).unwrap();
+        let e4 = game.node(game.root()).children()[0];
+        assert_eq!(game.node(e4).clock, Some(Duration::from_secs(300)));
+        assert_eq!(game.node(e4).comments, ["Best by test"]);
+        assert_eq!(game.node(e4).comments_before, ["Opening"]);
+
+        assert_eq!(game.to_pgn(), pgn);
+    }
+
+    #[test]
+    fn round_trips_comments_without_moves() {
+        let pgn = "{Game abandoned} 1-0";
+        let game = Game::from_pgn(pgn).unwrap();
+        assert_eq!(game.node(game.root()).comments, ["Game abandoned"]);
+        assert!(game.to_pgn().ends_with(&format!("\n\n{}\n", pgn)));
+        assert_eq!(Game::from_pgn(&game.to_pgn()).unwrap().to_pgn(), game.to_pgn());
+
+        let game = Game::from_pgn("1. e4 ({Anything but} {d4}) e5 *").unwrap();
+        assert!(game.to_pgn().ends_with("\n\n1. e4 {Anything but} {d4} 1... e5 *\n"));
+    }
+
+    #[test]
+    fn keeps_the_starting_position() {
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 30").unwrap();
+        let mv = parse_san(&board, "Kd7").unwrap();
+        board.push_move(mv);
+
+        let game = Game::from_position(board);
+        assert_eq!(game.board().to_fen(), "8/3k4/8/8/8/8/4P3/4K3 w - - 1 31");
+
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 30\"]\n"));
+        assert!(pgn.ends_with("\n30... Kd7 *\n"));
+
+        assert_eq!(Game::from_pgn(&pgn).unwrap().to_pgn(), pgn);
+    }
+}
@@ -0,0 +1,24 @@
+//! Theo's chess core: the board and its rules, moves, text notations and the engine that picks moves.
+//!
+//! ```
+//! use theo::Board;
+//! use theo::board::placement::parse_and_make_move;
+//!
+//! let mut board = Board::new();
+//! parse_and_make_move(&mut board, "e2 e4").unwrap();
+//!
+//! assert_eq!(board.to_fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+//! ```
+//!
+//! Nothing in here prints or reads from the terminal, that's left to whatever sits on top (see `main.rs`).
+
+pub mod board;
+pub mod engine;
+pub mod game;
+pub mod moves;
+pub mod notation;
+
+pub use board::board::{Board, Color, Piece};
+pub use board::square::Square;
+pub use game::Game;
+pub use moves::{Move, MoveError};
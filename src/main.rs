@@ -1,28 +1,285 @@
-mod board;
-mod bot;
 mod utils;
 
+use std::env;
+use std::fs;
+use std::time::{Duration, Instant};
+
 use colored::Colorize;
 
-use board::board::Board;
-use board::placement::parse_and_make_move;
-use bot::easy::choose_bot_move;
-use utils::board::{display_board, get_user_input};
+use theo::{Board, Color, Move, MoveError};
+use theo::board::placement::{parse_and_make_move, play_move};
+use theo::board::status::GameStatus;
+use theo::engine::easy::choose_bot_move;
+use theo::engine::search::{search, SearchLimits};
+use theo::notation::epd::parse_epd_file;
+use theo::notation::pgn::result_of;
+use theo::notation::san::{parse_san, to_san};
+use theo::notation::uci::to_uci;
+use utils::board::{display_board, display_game_result, get_user_input};
+use utils::pgn::{load_game, save_game};
+
+const DEFAULT_PGN_FILE: &str = "theo.pgn";
 
 fn main() {
-    let mut board = Board::new();
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if let Some(mode @ ("perft" | "divide")) = args.first().map(String::as_str) { // theo perft <depth> [fen]
+        run_perft(mode == "divide", &args[1..]);
+        return;
+    }
+
+    if args.first().map(String::as_str) == Some("epd") { // theo epd <file> [depth <plies>] [time <seconds>]
+        run_epd(&args[1..]);
+        return;
+    }
+
+    let mut board = match args.first() { // optionally start from a fen instead of the usual setup
+        Some(fen) => match Board::from_fen(fen) {
+            Ok(board) => board,
+            Err(e) => {
+                println!("{} {}", "Invalid FEN:".red().bold(), e);
+                return;
+            },
+        },
+        None => Board::new(),
+    };
 
     loop {
-        display_board(&mut board);
-
-        let user_move = get_user_input();
-        if parse_and_make_move(&mut board, &user_move, true) {
-            let mut bot_move = choose_bot_move(&mut board);
-             while !parse_and_make_move(&mut board, &bot_move, false) {
-                bot_move = choose_bot_move(&mut board);
-             }
+        if game_over(&board) {
+            offer_save(&board, result_of(board.status()));
+            break;
+        }
+
+        if board.side_to_move == Color::Black { // Theo plays black
+            if let Some(bot_move) = choose_bot_move(&board) {
+                let san = to_san(&board, bot_move);
+                if play_move(&mut board, bot_move).is_ok() {
+                    println!("Theo plays {} ({})", san, to_uci(bot_move));
+                }
+            }
+            continue;
+        }
+
+        display_board(&board);
+
+        let user_move = match get_user_input("Enter your move: ") {
+            Some(input) => input,
+            None => { // end of input, nobody left to play against
+                println!();
+                break;
+            },
+        };
+        if user_move.is_empty() {
+            continue;
+        }
+
+        if user_move == "draw" { // claim a fifty move or threefold repetition draw
+            match board.claimable_draw() {
+                Some(reason) => {
+                    display_game_result(GameStatus::Draw(reason));
+                    offer_save(&board, result_of(GameStatus::Draw(reason)));
+                    break;
+                },
+                None => {
+                    println!("{}", "There is no draw to claim right now.".red().bold());
+                    continue;
+                },
+            }
+        }
+
+        if user_move == "undo" { // take back our last move along with Theo's reply
+            if board.move_stack.len() >= 2 {
+                board.pop_move();
+                board.pop_move();
+            } else {
+                println!("{}", "There are no moves to undo.".red().bold());
+            }
+            continue;
+        }
+
+        if let Some(path) = save_path(&user_move) { // save the game so far, it's written as unfinished
+            save_game(&board, result_of(GameStatus::Ongoing), path);
+            continue;
+        }
+
+        if let Some(path) = user_move.strip_prefix("load ").map(str::trim) { // carry on from the end of a PGN game
+            if let Some(loaded) = load_game(path) {
+                board = loaded;
+            }
+            continue;
+        }
+
+        if user_move == "fen" {
+            println!("{}", board.to_fen());
+            continue;
+        }
+
+        if let Err(e) = make_user_move(&mut board, &user_move) {
+            println!("{} {}", "Invalid move:".red().bold(), e);
+        }
+    }
+}
+
+fn make_user_move(board: &mut Board, input: &str) -> Result<Move, MoveError> { // squares like "e2 e4" or "e2e4", or SAN like "Nf3"
+    match parse_and_make_move(board, input) {
+        Err(MoveError::MalformedNotation(_) | MoveError::InvalidSquare(_)) => {
+            let mv = parse_san(board, input)?;
+            play_move(board, mv)
+        },
+        result => result,
+    }
+}
+
+fn save_path(input: &str) -> Option<&str> { // "save" or "save <file>"
+    let rest = input.strip_prefix("save")?;
+    if rest.is_empty() {
+        return Some(DEFAULT_PGN_FILE);
+    }
+
+    rest.strip_prefix(' ').map(str::trim).filter(|path| !path.is_empty())
+}
+
+fn offer_save(board: &Board, result: &str) { // once the game is over there's only saving it left to do
+    while let Some(input) = get_user_input("Type save [file] to keep the game as PGN, or press enter to quit: ") {
+        match save_path(&input) {
+            Some(path) => {
+                save_game(board, result, path);
+                return;
+            },
+            None if input.is_empty() => return,
+            None => println!("{}", "The game is over, only save is left.".red().bold()),
+        }
+    }
+}
+
+fn game_over(board: &Board) -> bool {
+    let status = board.status();
+    if status == GameStatus::Ongoing {
+        return false;
+    }
+
+    display_board(board);
+    display_game_result(status);
+    true
+}
+
+fn run_perft(divide: bool, args: &[String]) {
+    let depth = match args.first().and_then(|depth| depth.parse::<u32>().ok()) {
+        Some(depth) => depth,
+        None => {
+            println!("{}", "Usage: theo perft|divide <depth> [fen]".red().bold());
+            return;
+        },
+    };
+
+    let board = match args.get(1) {
+        Some(fen) => Board::from_fen(fen),
+        None => Ok(Board::new()),
+    };
+    let mut board = match board {
+        Ok(board) => board,
+        Err(e) => {
+            println!("{} {}", "Invalid FEN:".red().bold(), e);
+            return;
+        },
+    };
+
+    let start = Instant::now();
+    let nodes = if divide {
+        let counts = board.divide(depth);
+        for (mv, nodes) in &counts {
+            println!("{}: {}", mv, nodes);
+        }
+        println!();
+        counts.iter().map(|(_, nodes)| nodes).sum()
+    } else {
+        board.perft(depth)
+    };
+    let elapsed = start.elapsed();
+
+    println!("Nodes: {}", nodes);
+    println!("Time: {:.3}s ({:.0} nodes/s)", elapsed.as_secs_f64(), nodes as f64 / elapsed.as_secs_f64().max(1e-9));
+}
+
+fn run_epd(args: &[String]) {
+    let usage = || println!("{}", "Usage: theo epd <file> [depth <plies>] [time <seconds>]".red().bold());
+    let Some(path) = args.first() else {
+        return usage();
+    };
+
+    let mut limits = SearchLimits { depth: None, time: None };
+    for option in args[1..].chunks(2) {
+        match option {
+            [name, value] if name == "depth" => match value.parse() {
+                Ok(depth) => limits.depth = Some(depth),
+                Err(_) => return usage(),
+            },
+            [name, value] if name == "time" => match value.parse::<f64>().ok().and_then(|seconds| Duration::try_from_secs_f64(seconds).ok()) {
+                Some(time) => limits.time = Some(time),
+                None => return usage(),
+            },
+            _ => return usage(),
+        }
+    }
+    if limits.depth.is_none() && limits.time.is_none() {
+        limits.time = Some(Duration::from_secs(1));
+    }
+
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            println!("{} {}", "Could not read the test suite:".red().bold(), e);
+            return;
+        },
+    };
+
+    let start = Instant::now();
+    let (mut solved, mut tried) = (0, 0);
+    for (index, epd) in parse_epd_file(&text).into_iter().enumerate() {
+        let epd = match epd {
+            Ok(epd) => epd,
+            Err((line, e)) => {
+                println!("{} line {}: {}", "Skipped".yellow().bold(), line, e);
+                continue;
+            },
+        };
+        let name = epd.id.clone().unwrap_or_else(|| format!("#{}", index + 1));
+
+        let result = search(&epd.board, limits);
+        let Some(best_move) = result.best_move else {
+            println!("{}: {}", name, "no legal moves, skipped".yellow());
+            continue;
+        };
+
+        // bm and am decide it when there are any, otherwise a mate quick enough for dm does
+        let ok = match (epd.is_solved_by(best_move), epd.direct_mate) {
+            (Some(ok), _) => ok,
+            (None, Some(moves)) => result.mate_in().is_some_and(|found| found > 0 && found as u32 <= moves),
+            (None, None) => {
+                println!("{}: {}", name, "nothing to solve, skipped".yellow());
+                continue;
+            },
+        };
+
+        tried += 1;
+        let san = to_san(&epd.board, best_move);
+        if ok {
+            solved += 1;
+            println!("{}: {} {}", name, san, "ok".green());
         } else {
-            println!("{}", "Invalid move! Please try again.".red().bold());
+            let expected: Vec<String> = epd.best_moves.iter().map(|&mv| to_san(&epd.board, mv)).collect();
+            let avoided: Vec<String> = epd.avoid_moves.iter().map(|&mv| to_san(&epd.board, mv)).collect();
+            let wanted = match (expected.is_empty(), epd.direct_mate) {
+                (false, _) => format!("expected {}", expected.join(" ")),
+                (true, _) if !avoided.is_empty() => format!("should avoid {}", avoided.join(" ")),
+                (true, moves) => format!("expected mate in {}", moves.unwrap_or_default()),
+            };
+            println!("{}: {} {}, {}", name, san, "failed".red(), wanted);
         }
     }
+
+    let rate = if tried == 0 { 0.0 } else { 100.0 * solved as f64 / tried as f64 };
+    println!();
+    println!("Solved: {}/{} ({:.1}%)", solved, tried, rate);
+    println!("Time: {:.3}s", start.elapsed().as_secs_f64());
 }
@@ -0,0 +1,195 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::board::board::Piece;
+use crate::board::square::Square;
+
+// packed into 16 bits: from square in bits 0-5, to square in bits 6-11 and the kind of move in bits 12-15
+/// A move packed into 16 bits, see the layout above. `Display` and `FromStr` use long algebraic text like `e2e4` or `e7e8q`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Move(u16);
+
+impl Move {
+    pub const QUIET: u16 = 0;
+    pub const DOUBLE_PAWN_PUSH: u16 = 1;
+    pub const KING_CASTLE: u16 = 2;
+    pub const QUEEN_CASTLE: u16 = 3;
+    pub const CAPTURE: u16 = 4;
+    pub const EN_PASSANT: u16 = 5;
+    /// The low two bits pick the piece, see `promotion_flags`.
+    pub const PROMOTION: u16 = 8;
+    pub const PROMOTION_CAPTURE: u16 = 12;
+
+    /// Packs a move, `flags` is one of the constants above.
+    pub fn new(from: Square, to: Square, flags: u16) -> Self {
+        Move((from.index() as u16) | ((to.index() as u16) << 6) | (flags << 12))
+    }
+
+    /// The flags for promoting to `piece`, anything but a knight, bishop or rook counts as a queen.
+    pub fn promotion_flags(piece: Piece, capture: bool) -> u16 {
+        let base = if capture { Move::PROMOTION_CAPTURE } else { Move::PROMOTION };
+        base | match piece {
+            Piece::N => 0,
+            Piece::B => 1,
+            Piece::R => 2,
+            _ => 3,
+        }
+    }
+
+    /// The square the piece moves from.
+    pub fn from(self) -> Square {
+        Square::new((self.0 & 0x3f) as usize)
+    }
+
+    /// The square the piece lands on.
+    pub fn to(self) -> Square {
+        Square::new(((self.0 >> 6) & 0x3f) as usize)
+    }
+
+    /// The kind of move, one of the constants above.
+    pub fn flags(self) -> u16 {
+        self.0 >> 12
+    }
+
+    /// The piece a pawn turns into, if this is a promotion.
+    pub fn promotion(self) -> Option<Piece> {
+        if self.flags() & Move::PROMOTION == 0 {
+            return None;
+        }
+
+        Some(match self.flags() & 3 {
+            0 => Piece::N,
+            1 => Piece::B,
+            2 => Piece::R,
+            _ => Piece::Q,
+        })
+    }
+
+    /// True for every capture, en passant and capturing promotions included.
+    pub fn is_capture(self) -> bool {
+        self.flags() & Move::CAPTURE != 0
+    }
+
+    pub fn is_en_passant(self) -> bool {
+        self.flags() == Move::EN_PASSANT
+    }
+
+    pub fn is_double_pawn_push(self) -> bool {
+        self.flags() == Move::DOUBLE_PAWN_PUSH
+    }
+}
+
+/// Why a move couldn't be read or played.
+/// `Display` gives a message fit to show a player.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MoveError {
+    MalformedNotation(String),
+    MalformedUci(String),
+    InvalidSquare(String),
+    InvalidPromotion(String),
+    NoPieceOnSquare(Square),
+    WrongColor(Square), // there's a piece there but it belongs to the side not to move
+    CapturesOwnPiece { from: Square, to: Square },
+    IllegalForPiece { piece: Piece, from: Square, to: Square },
+    PathBlocked { piece: Piece, from: Square, to: Square },
+    PromotionPieceNeeded { from: Square, to: Square },
+    NotAPromotion { from: Square, to: Square },
+    NoMatchingMove(String),
+    AmbiguousMove(String),
+    CastlingNotAllowed,
+    LeavesKingInCheck,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::MalformedNotation(text) => write!(f, "'{}' isn't a move, try something like 'Nf3', 'exd5', 'O-O' or 'e2 e4'", text),
+            MoveError::MalformedUci(text) => write!(f, "'{}' isn't a UCI move, give the two squares and any promotion piece with no spaces, like 'e2e4', 'e7e8q' or 'e1g1' to castle", text),
+            MoveError::InvalidSquare(text) => write!(f, "'{}' isn't a square, use a file a-h and a rank 1-8", text),
+            MoveError::InvalidPromotion(text) => write!(f, "'{}' isn't a piece a pawn can promote to, use one of q, r, b or n", text),
+            MoveError::NoPieceOnSquare(square) => write!(f, "there's no piece on {}", square),
+            MoveError::WrongColor(square) => write!(f, "the piece on {} belongs to your opponent", square),
+            MoveError::CapturesOwnPiece { from, to } => write!(f, "the piece on {} can't capture your own piece on {}", from, to),
+            MoveError::IllegalForPiece { piece, from, to } => write!(f, "a {} can't move from {} to {}", piece_name(*piece), from, to),
+            MoveError::PathBlocked { piece, from, to } => write!(f, "the {} on {} is blocked on its way to {}", piece_name(*piece), from, to),
+            MoveError::PromotionPieceNeeded { from, to } => write!(f, "say what to promote to, like '{}{}q' for a queen", from, to),
+            MoveError::NotAPromotion { from, to } => write!(f, "{} to {} isn't a promotion, leave the piece off", from, to),
+            MoveError::NoMatchingMove(text) => write!(f, "no legal move matches '{}'", text),
+            MoveError::AmbiguousMove(text) => write!(f, "'{}' could be more than one move, add the file or rank the piece is on", text),
+            MoveError::CastlingNotAllowed => write!(f, "castling isn't allowed right now"),
+            MoveError::LeavesKingInCheck => write!(f, "that move would leave your king in check"),
+        }
+    }
+}
+
+impl fmt::Display for Move { // long algebraic like e2e4 or e7e8q
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.from(), self.to())?;
+
+        if let Some(piece) = self.promotion() {
+            write!(f, "{}", piece_letter(piece).to_ascii_lowercase())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Move { // only from, to and promotion come out of text, find_legal_move fills in the other flags from a board
+    type Err = MoveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.is_ascii() || s.contains(char::is_whitespace) || (s.len() != 4 && s.len() != 5) {
+            return Err(MoveError::MalformedNotation(s.to_string()));
+        }
+
+        let from: Square = s[0..2].parse()?;
+        let to: Square = s[2..4].parse()?;
+
+        let flags = match s[4..].chars().next() {
+            None => Move::QUIET,
+            Some(c) => match piece_from_letter(c.to_ascii_uppercase()) {
+                Some(piece @ (Piece::Q | Piece::R | Piece::B | Piece::N)) => Move::promotion_flags(piece, false),
+                _ => return Err(MoveError::InvalidPromotion(c.to_string())),
+            },
+        };
+
+        Ok(Move::new(from, to, flags))
+    }
+}
+
+/// The uppercase letter for a piece as written in FEN and SAN.
+pub fn piece_letter(piece: Piece) -> char {
+    match piece {
+        Piece::K => 'K',
+        Piece::Q => 'Q',
+        Piece::R => 'R',
+        Piece::B => 'B',
+        Piece::N => 'N',
+        Piece::P => 'P',
+    }
+}
+
+/// The lowercase English name of a piece, for messages.
+pub fn piece_name(piece: Piece) -> &'static str {
+    match piece {
+        Piece::K => "king",
+        Piece::Q => "queen",
+        Piece::R => "rook",
+        Piece::B => "bishop",
+        Piece::N => "knight",
+        Piece::P => "pawn",
+    }
+}
+
+/// The piece an uppercase letter stands for.
+pub fn piece_from_letter(letter: char) -> Option<Piece> {
+    match letter {
+        'K' => Some(Piece::K),
+        'Q' => Some(Piece::Q),
+        'R' => Some(Piece::R),
+        'B' => Some(Piece::B),
+        'N' => Some(Piece::N),
+        'P' => Some(Piece::P),
+        _ => None,
+    }
+}
@@ -0,0 +1,224 @@
+//! Extended Position Description, the format test suites like WAC and STS come in. A line is the first
+//! four FEN fields followed by operations, each an opcode, some operands and a `;`.
+
+use std::fmt;
+
+use crate::{Board, Move, MoveError};
+use crate::notation::fen::FenError;
+use crate::notation::san::parse_san;
+
+/// A position read from an EPD line. Every operation is kept in `operations`, and the ones test
+/// suites rely on are also picked out.
+#[derive(Debug, Clone)]
+pub struct Epd {
+    /// The position, with its clocks from `hmvc` and `fmvn` when they're given.
+    pub board: Board,
+    /// Every operation in the order written, quotes taken off string operands.
+    pub operations: Vec<(String, Vec<String>)>,
+    /// `bm`, the moves that solve the position.
+    pub best_moves: Vec<Move>,
+    /// `am`, moves that don't.
+    pub avoid_moves: Vec<Move>,
+    /// `id`, the name of the position within its suite.
+    pub id: Option<String>,
+    /// `dm`, the side to move mates in this many moves.
+    pub direct_mate: Option<u32>,
+    /// `c0`, the first comment.
+    pub comment: Option<String>,
+}
+
+/// Why an EPD line couldn't be read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EpdError {
+    Fen(FenError),
+    Malformed(String),
+    /// A `bm` or `am` move that isn't legal in the position.
+    InvalidMove { opcode: String, error: MoveError },
+    InvalidOperand { opcode: String, operand: String },
+}
+
+impl fmt::Display for EpdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EpdError::Fen(e) => write!(f, "invalid position: {}", e),
+            EpdError::Malformed(message) => write!(f, "{}", message),
+            EpdError::InvalidMove { opcode, error } => write!(f, "bad move in {}: {}", opcode, error),
+            EpdError::InvalidOperand { opcode, operand } => write!(f, "'{}' is not a valid operand for {}", operand, opcode),
+        }
+    }
+}
+
+impl Epd {
+    /// The operands of an operation, if the line has it.
+    pub fn operation(&self, opcode: &str) -> Option<&[String]> {
+        self.operations.iter().find(|(name, _)| name == opcode).map(|(_, operands)| operands.as_slice())
+    }
+
+    /// Whether playing `mv` solves the position by its `bm` and `am` operations, `None` when it has
+    /// neither.
+    pub fn is_solved_by(&self, mv: Move) -> Option<bool> {
+        if self.best_moves.is_empty() && self.avoid_moves.is_empty() {
+            return None;
+        }
+
+        Some((self.best_moves.is_empty() || self.best_moves.contains(&mv)) && !self.avoid_moves.contains(&mv))
+    }
+}
+
+/// Reads one EPD line.
+pub fn parse_epd(line: &str) -> Result<Epd, EpdError> {
+    let line = line.trim();
+    let mut fields = Vec::new();
+    let mut rest = line;
+    for _ in 0..4 {
+        let (field, after) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        if field.is_empty() {
+            return Err(EpdError::Malformed(format!("expected 4 position fields before the operations in '{}'", line)));
+        }
+        fields.push(field);
+        rest = after.trim_start();
+    }
+
+    let operations = parse_operations(rest)?;
+
+    // the clocks aren't part of the position in EPD but can be given as operations
+    let clock = |opcode: &str, default: &str| -> Result<String, EpdError> {
+        match operations.iter().find(|(name, _)| name == opcode) {
+            Some((_, operands)) => match operands.as_slice() {
+                [number] if number.parse::<u32>().is_ok() => Ok(number.clone()),
+                _ => Err(EpdError::InvalidOperand { opcode: opcode.to_string(), operand: operands.join(" ") }),
+            },
+            None => Ok(default.to_string()),
+        }
+    };
+    let fen = format!("{} {} {}", fields.join(" "), clock("hmvc", "0")?, clock("fmvn", "1")?);
+    let board = Board::from_fen(&fen).map_err(EpdError::Fen)?;
+
+    let moves = |opcode: &str| -> Result<Vec<Move>, EpdError> {
+        let operands = operations.iter().filter(|(name, _)| name == opcode).flat_map(|(_, operands)| operands);
+        operands
+            .map(|san| parse_san(&board, san).map_err(|error| EpdError::InvalidMove { opcode: opcode.to_string(), error }))
+            .collect()
+    };
+    let first = |opcode: &str| operations.iter().find(|(name, _)| name == opcode).and_then(|(_, operands)| operands.first()).cloned();
+
+    let direct_mate = match first("dm") {
+        Some(moves) => match moves.parse() {
+            Ok(moves) => Some(moves),
+            Err(_) => return Err(EpdError::InvalidOperand { opcode: "dm".to_string(), operand: moves }),
+        },
+        None => None,
+    };
+
+    Ok(Epd {
+        best_moves: moves("bm")?,
+        avoid_moves: moves("am")?,
+        id: first("id"),
+        direct_mate,
+        comment: first("c0"),
+        board,
+        operations,
+    })
+}
+
+/// Reads every position in a file of EPD lines, skipping blank ones and `#` comments. Errors
+/// come with the line number they're on.
+pub fn parse_epd_file(text: &str) -> Vec<Result<Epd, (usize, EpdError)>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(index, line)| parse_epd(line).map_err(|e| (index + 1, e)))
+        .collect()
+}
+
+fn parse_operations(text: &str) -> Result<Vec<(String, Vec<String>)>, EpdError> {
+    let mut operations = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let mut opcode = String::new();
+        while let Some(c) = chars.next_if(|&c| c.is_ascii_alphanumeric() || c == '_') {
+            opcode.push(c);
+        }
+        if opcode.is_empty() {
+            return Err(EpdError::Malformed(format!("expected an opcode, found '{}'", chars.collect::<String>())));
+        }
+
+        let mut operands = Vec::new();
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            match chars.next() {
+                Some(';') => break,
+                None => return Err(EpdError::Malformed(format!("the {} operation is missing its ';'", opcode))),
+                Some('"') => { // strings can hold spaces and semicolons
+                    let mut operand = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some(c) => operand.push(c),
+                            None => return Err(EpdError::Malformed(format!("a string in {} never ends", opcode))),
+                        }
+                    }
+                    operands.push(operand);
+                },
+                Some(c) => {
+                    let mut operand = c.to_string();
+                    while let Some(c) = chars.next_if(|&c| !c.is_whitespace() && c != ';') {
+                        operand.push(c);
+                    }
+                    operands.push(operand);
+                },
+            }
+        }
+
+        operations.push((opcode, operands));
+    }
+
+    Ok(operations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::san::to_san;
+
+    #[test]
+    fn reads_test_suite_lines() {
+        let epd = parse_epd(r#"2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id "WAC.001"; c0 "mate; quickly";"#).unwrap();
+
+        assert_eq!(epd.id.as_deref(), Some("WAC.001"));
+        assert_eq!(epd.comment.as_deref(), Some("mate; quickly"));
+        assert_eq!(epd.best_moves.len(), 1);
+        assert_eq!(to_san(&epd.board, epd.best_moves[0]), "Qg6");
+        assert_eq!(epd.operation("bm"), Some(&["Qg6".to_string()][..]));
+        assert_eq!(epd.is_solved_by(epd.best_moves[0]), Some(true));
+    }
+
+    #[test]
+    fn reads_clocks_and_other_opcodes() {
+        let epd = parse_epd("4k3/8/8/8/8/8/8/R3K3 w Q - am Kd1 Kf1; dm 3; hmvc 12; fmvn 40;").unwrap();
+
+        assert_eq!(epd.board.to_fen(), "4k3/8/8/8/8/8/8/R3K3 w Q - 12 40");
+        assert_eq!(epd.avoid_moves.len(), 2);
+        assert_eq!(epd.direct_mate, Some(3));
+        assert_eq!(epd.id, None);
+
+        let castle = parse_san(&epd.board, "O-O-O").unwrap();
+        assert_eq!(epd.is_solved_by(castle), Some(true));
+        assert_eq!(epd.is_solved_by(epd.avoid_moves[0]), Some(false));
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        assert!(matches!(parse_epd("8/8/8/8/8/8/8/8 w -"), Err(EpdError::Malformed(_))));
+        assert!(matches!(parse_epd("4k3/8/8/8/8/8/8/4K3 w - - bm Kd3;"), Err(EpdError::InvalidMove { .. })));
+        assert!(matches!(parse_epd("4k3/8/8/8/8/8/8/4K3 w - - dm x;"), Err(EpdError::InvalidOperand { .. })));
+        assert!(matches!(parse_epd("4k3/8/8/8/8/8/8/4K3 w - - id \"open"), Err(EpdError::Malformed(_))));
+        assert!(matches!(parse_epd("4k3/8/8/8/8/8/8/4K3 w - - id x"), Err(EpdError::Malformed(_))));
+    }
+}
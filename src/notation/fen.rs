@@ -0,0 +1,315 @@
+use std::fmt;
+
+use crate::Board;
+use crate::board::bitboard::squares;
+use crate::board::board::{Color, Piece};
+use crate::board::square::Square;
+use crate::moves::{piece_from_letter, piece_letter};
+
+/// The usual starting position.
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+/// What was wrong with a FEN string `Board::from_fen` turned down.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FenError {
+    WrongFieldCount(usize),
+    WrongRankCount(usize),
+    WrongSquareCount { rank: usize, squares: usize }, // rank as written in the fen, 8 first
+    InvalidPiece(char),
+    InvalidSideToMove(String),
+    InvalidCastling(String),
+    InvalidEnPassant(String),
+    InvalidHalfmoveClock(String),
+    InvalidFullmoveNumber(String),
+    WrongKingCount { white: u32, black: u32 },
+    PawnOnBackRank(String),
+    OpponentInCheck, // the side that just moved can't have left its king attacked
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::WrongFieldCount(count) => write!(f, "expected 6 space separated fields (4 at least), found {}", count),
+            FenError::WrongRankCount(count) => write!(f, "expected 8 ranks separated by '/', found {}", count),
+            FenError::WrongSquareCount { rank, squares } => write!(f, "rank {} describes {} squares instead of 8", rank, squares),
+            FenError::InvalidPiece(c) => write!(f, "'{}' is not a piece, use one of KQRBNP or kqrbnp", c),
+            FenError::InvalidSideToMove(field) => write!(f, "side to move must be 'w' or 'b', found '{}'", field),
+            FenError::InvalidCastling(field) => write!(f, "castling rights must be '-' or some of 'KQkq', found '{}'", field),
+            FenError::InvalidEnPassant(field) => write!(f, "en passant square must be '-' or a square on rank 3 or 6, found '{}'", field),
+            FenError::InvalidHalfmoveClock(field) => write!(f, "halfmove clock must be a number below {}, found '{}'", u32::MAX, field),
+            FenError::InvalidFullmoveNumber(field) => write!(f, "fullmove number must be a number from 1 to {}, found '{}'", u32::MAX - 1, field),
+            FenError::WrongKingCount { white, black } => write!(f, "each side needs exactly one king, found {} white and {} black", white, black),
+            FenError::PawnOnBackRank(square) => write!(f, "there's a pawn on {}, pawns can't stand on the first or last rank", square),
+            FenError::OpponentInCheck => write!(f, "the side that isn't to move is in check"),
+        }
+    }
+}
+
+impl Board {
+    /// Sets up a board from a FEN string. The clocks may be left off, and castling rights or an en passant square the pieces
+    /// don't allow are dropped.
+    pub fn from_fen(fen: &str) -> Result<Board, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() < 4 || fields.len() > 6 { // the clocks are often left off, they default to 0 and 1
+            return Err(FenError::WrongFieldCount(fields.len()));
+        }
+
+        let mut board = Board::empty();
+
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::WrongRankCount(ranks.len()));
+        }
+
+        for (i, rank_text) in ranks.iter().enumerate() {
+            let rank = 7 - i; // fen starts from rank 8
+            let mut file = 0;
+
+            for c in rank_text.chars() {
+                if let Some(empty) = c.to_digit(10).filter(|&n| (1..=8).contains(&n)) {
+                    file += empty as usize;
+                    continue;
+                }
+
+                let piece = piece_from_letter(c.to_ascii_uppercase()).ok_or(FenError::InvalidPiece(c))?;
+                let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+                if let Some(square) = Square::from_file_rank(file, rank) {
+                    board.put_piece(square, piece, color);
+                }
+                file += 1;
+            }
+
+            if file != 8 {
+                return Err(FenError::WrongSquareCount { rank: rank + 1, squares: file });
+            }
+        }
+
+        board.side_to_move = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            other => return Err(FenError::InvalidSideToMove(other.to_string())),
+        };
+
+        if fields[2] != "-" {
+            for c in fields[2].chars() {
+                let right = match c {
+                    'K' => &mut board.castling.w_kingside,
+                    'Q' => &mut board.castling.w_queenside,
+                    'k' => &mut board.castling.b_kingside,
+                    'q' => &mut board.castling.b_queenside,
+                    _ => return Err(FenError::InvalidCastling(fields[2].to_string())),
+                };
+
+                if *right { // listed twice
+                    return Err(FenError::InvalidCastling(fields[2].to_string()));
+                }
+                *right = true;
+            }
+        }
+
+        // plenty of fens in the wild keep rights the pieces no longer allow, those just get dropped
+        let home = |square: usize, piece: Piece, color: Color| board.piece_at(Square::new(square)) == Some((piece, color));
+        let (w_king_home, b_king_home) = (home(4, Piece::K, Color::White), home(60, Piece::K, Color::Black));
+        let w_kingside = w_king_home && home(7, Piece::R, Color::White);
+        let w_queenside = w_king_home && home(0, Piece::R, Color::White);
+        let b_kingside = b_king_home && home(63, Piece::R, Color::Black);
+        let b_queenside = b_king_home && home(56, Piece::R, Color::Black);
+
+        board.castling.w_kingside &= w_kingside;
+        board.castling.w_queenside &= w_queenside;
+        board.castling.b_kingside &= b_kingside;
+        board.castling.b_queenside &= b_queenside;
+
+        if fields[3] != "-" {
+            let square = match fields[3].parse::<Square>() {
+                Ok(square) if square.relative_rank(board.side_to_move) == 5 => square,
+                _ => return Err(FenError::InvalidEnPassant(fields[3].to_string())),
+            };
+
+            // only kept if a pawn really just pushed two squares past it, like castling rights above
+            let color = board.side_to_move;
+            let pushed = square.forward(!color, 1).and_then(|pawn| board.piece_at(pawn)) == Some((Piece::P, !color));
+            let passed_over = board.piece_at(square).is_none() && square.forward(color, 1).is_some_and(|origin| board.piece_at(origin).is_none());
+            if pushed && passed_over {
+                board.en_passant = Some(square);
+            }
+        }
+
+        // both go up by one with the next move, so the largest u32 is out too
+        if let Some(field) = fields.get(4) {
+            board.halfmove_clock = match field.parse() {
+                Ok(clock) if clock < u32::MAX => clock,
+                _ => return Err(FenError::InvalidHalfmoveClock(field.to_string())),
+            };
+        }
+
+        if let Some(field) = fields.get(5) {
+            board.fullmove_number = match field.parse() {
+                Ok(number) if (1..u32::MAX).contains(&number) => number,
+                _ => return Err(FenError::InvalidFullmoveNumber(field.to_string())),
+            };
+        }
+
+        let (white_kings, black_kings) = (board.pieces_of(Piece::K, Color::White), board.pieces_of(Piece::K, Color::Black));
+        if white_kings.count_ones() != 1 || black_kings.count_ones() != 1 {
+            return Err(FenError::WrongKingCount { white: white_kings.count_ones(), black: black_kings.count_ones() });
+        }
+
+        let back_rank_pawns = board.pieces[Piece::P as usize] & 0xff00_0000_0000_00ff; // ranks 1 and 8
+        if let Some(square) = squares(back_rank_pawns).next() {
+            return Err(FenError::PawnOnBackRank(square.to_string()));
+        }
+
+        if board.in_check(!board.side_to_move) {
+            return Err(FenError::OpponentInCheck);
+        }
+
+        board.hash = board.compute_hash();
+        board.position_history.push(board.hash);
+
+        Ok(board)
+    }
+
+    /// The position as a FEN string.
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+
+            for file in 0..8 {
+                match self.piece_at(Square::new(rank * 8 + file)) {
+                    Some((piece, color)) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+
+                        let letter = piece_letter(piece);
+                        fen.push(if color == Color::White { letter } else { letter.to_ascii_lowercase() });
+                    },
+                    None => empty += 1,
+                }
+            }
+
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                fen.push('/');
+            }
+        }
+
+        fen.push_str(if self.side_to_move == Color::White { " w " } else { " b " });
+
+        let rights = [
+            (self.castling.w_kingside, 'K'),
+            (self.castling.w_queenside, 'Q'),
+            (self.castling.b_kingside, 'k'),
+            (self.castling.b_queenside, 'q'),
+        ];
+        let castling: String = rights.iter().filter(|(right, _)| *right).map(|(_, c)| c).collect();
+        fen.push_str(if castling.is_empty() { "-" } else { &castling });
+
+        match self.en_passant {
+            Some(square) => fen.push_str(&format!(" {}", square)),
+            None => fen.push_str(" -"),
+        }
+
+        fen.push_str(&format!(" {} {}", self.halfmove_clock, self.fullmove_number));
+
+        fen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::move_generation::generate_valid_moves;
+    use crate::notation::san::parse_san;
+
+    fn error(fen: &str) -> FenError {
+        Board::from_fen(fen).unwrap_err()
+    }
+
+    #[test]
+    fn positions_round_trip() {
+        for fen in [
+            START_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b Kq d3 0 3",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 12 40",
+        ] {
+            assert_eq!(Board::from_fen(fen).unwrap().to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn clocks_are_optional() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/4K3 b - -").unwrap();
+        assert_eq!(board.to_fen(), "4k3/8/8/8/8/8/8/4K3 b - - 0 1");
+
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 7").unwrap();
+        assert_eq!(board.to_fen(), "4k3/8/8/8/8/8/8/4K3 w - - 7 1");
+    }
+
+    #[test]
+    fn castling_rights_the_pieces_dont_allow_are_dropped() {
+        let board = Board::from_fen("r3k3/8/8/8/8/8/8/4K2R w KQkq - 0 1").unwrap();
+        assert_eq!(board.to_fen(), "r3k3/8/8/8/8/8/8/4K2R w Kq - 0 1");
+
+        let board = Board::from_fen("r3k2r/8/8/8/8/8/8/R2K3R w KQkq - 0 1").unwrap();
+        assert_eq!(board.to_fen(), "r3k2r/8/8/8/8/8/8/R2K3R w kq - 0 1");
+    }
+
+    #[test]
+    fn en_passant_squares_without_a_double_push_are_dropped() {
+        // no pawn in front of e6 that could have just pushed past it
+        let board = Board::from_fen("4k3/8/8/3P4/8/8/8/4K3 w - e6 0 1").unwrap();
+        assert_eq!(board.en_passant, None);
+        assert!(generate_valid_moves(&board).iter().all(|mv| !mv.is_en_passant()));
+
+        // a pawn on e5, but e6 and e7 aren't empty so it can't have come from e7
+        let board = Board::from_fen("4k3/4p3/4p3/3PP3/8/8/8/4K3 w - e6 0 1").unwrap();
+        assert_eq!(board.en_passant, None);
+        assert!(generate_valid_moves(&board).iter().all(|mv| !mv.is_en_passant()));
+
+        // white's own pawn in front of the square doesn't count either
+        let board = Board::from_fen("4k3/8/8/3PP3/8/8/8/4K3 w - e6 0 1").unwrap();
+        assert_eq!(board.en_passant, None);
+
+        let board = Board::from_fen("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1").unwrap();
+        assert_eq!(board.en_passant, Some("e6".parse().unwrap()));
+    }
+
+    #[test]
+    fn the_largest_clocks_can_still_go_up() {
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/8/4K3 b - - 4294967294 4294967294").unwrap();
+        let mv = parse_san(&board, "Kd7").unwrap();
+        board.push_move(mv);
+        assert_eq!(board.to_fen(), "8/3k4/8/8/8/8/8/4K3 w - - 4294967295 4294967295");
+    }
+
+    #[test]
+    fn says_what_is_wrong() {
+        assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w"), FenError::WrongFieldCount(2));
+        assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w - - 0 1 extra"), FenError::WrongFieldCount(7));
+        assert_eq!(error("4k3/8/8/8/8/8/4K3 w - - 0 1"), FenError::WrongRankCount(7));
+        assert_eq!(error("4k3/8/8/8/8/8/8/4K2 w - - 0 1"), FenError::WrongSquareCount { rank: 1, squares: 7 });
+        assert_eq!(error("4k4/8/8/8/8/8/8/4K3 w - - 0 1"), FenError::WrongSquareCount { rank: 8, squares: 9 });
+        assert_eq!(error("4k3/8/8/8/8/8/8/4K2X w - - 0 1"), FenError::InvalidPiece('X'));
+        assert_eq!(error("4k3/8/8/8/8/8/8/4K3 x - - 0 1"), FenError::InvalidSideToMove("x".to_string()));
+        assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w KX - 0 1"), FenError::InvalidCastling("KX".to_string()));
+        assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w KK - 0 1"), FenError::InvalidCastling("KK".to_string()));
+        assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w - e3 0 1"), FenError::InvalidEnPassant("e3".to_string()));
+        assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w - z9 0 1"), FenError::InvalidEnPassant("z9".to_string()));
+        assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w - - x 1"), FenError::InvalidHalfmoveClock("x".to_string()));
+        assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w - - 0 0"), FenError::InvalidFullmoveNumber("0".to_string()));
+        assert_eq!(error("4k3/8/8/8/8/8/8/4K3 w - - 4294967295 1"), FenError::InvalidHalfmoveClock("4294967295".to_string()));
+        assert_eq!(error("4k3/8/8/8/8/8/8/4K3 b - - 0 4294967295"), FenError::InvalidFullmoveNumber("4294967295".to_string()));
+        assert_eq!(error("4k3/8/8/8/8/8/8/3KK3 w - - 0 1"), FenError::WrongKingCount { white: 2, black: 1 });
+        assert_eq!(error("8/8/8/8/8/8/8/4K3 w - - 0 1"), FenError::WrongKingCount { white: 1, black: 0 });
+        assert_eq!(error("4k2P/8/8/8/8/8/8/4K3 w - - 0 1"), FenError::PawnOnBackRank("h8".to_string()));
+        assert_eq!(error("4r1k1/8/8/8/8/8/8/4K3 b - - 0 1"), FenError::OpponentInCheck);
+    }
+}
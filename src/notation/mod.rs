@@ -0,0 +1,7 @@
+//! Reading and writing positions and moves as text.
+
+pub mod epd;
+pub mod fen;
+pub mod pgn;
+pub mod san;
+pub mod uci;
@@ -0,0 +1,595 @@
+//! Portable Game Notation, for saving games so other chess software can read them and loading
+//! games from it.
+
+use std::fmt;
+use std::iter::Peekable;
+use std::mem;
+use std::str::Chars;
+use std::vec::IntoIter;
+
+use crate::{Board, Color, Game, Move, MoveError};
+use crate::board::status::GameStatus;
+use crate::notation::fen::FenError;
+use crate::notation::san::parse_san;
+
+/// The Seven Tag Roster every PGN game starts with. Unknown values are written as `?` the way the
+/// standard asks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgnTags {
+    pub event: String,
+    pub site: String,
+    /// `YYYY.MM.DD`, with `??` for any part that isn't known.
+    pub date: String,
+    pub round: String,
+    pub white: String,
+    pub black: String,
+    /// `1-0`, `0-1`, `1/2-1/2` or `*` while the game is still going.
+    pub result: String,
+}
+
+impl Default for PgnTags {
+    fn default() -> Self {
+        PgnTags {
+            event: "?".to_string(),
+            site: "?".to_string(),
+            date: "????.??.??".to_string(),
+            round: "?".to_string(),
+            white: "?".to_string(),
+            black: "?".to_string(),
+            result: "*".to_string(),
+        }
+    }
+}
+
+impl PgnTags {
+    // the tags with their names, in the order they're written
+    pub(crate) fn roster(&self) -> [(&'static str, &str); 7] {
+        [
+            ("Event", &self.event),
+            ("Site", &self.site),
+            ("Date", &self.date),
+            ("Round", &self.round),
+            ("White", &self.white),
+            ("Black", &self.black),
+            ("Result", &self.result),
+        ]
+    }
+}
+
+/// The PGN result for a game in this state, `*` while it's still going.
+pub fn result_of(status: GameStatus) -> &'static str {
+    match status {
+        GameStatus::Ongoing => "*",
+        GameStatus::Checkmate(Color::White) => "1-0",
+        GameStatus::Checkmate(Color::Black) => "0-1",
+        GameStatus::Stalemate | GameStatus::Draw(_) => "1/2-1/2",
+    }
+}
+
+/// Writes every move played on `board` with `push_move` as a PGN game. Games that didn't start from
+/// the usual position get `SetUp` and `FEN` tags so they can be replayed.
+pub fn to_pgn(board: &Board, tags: &PgnTags) -> String {
+    let mut game = Game::from_position(board.clone());
+    for (name, value) in tags.roster() {
+        game.set_tag(name, value);
+    }
+    game.to_pgn()
+}
+
+// "12." before white's moves, "12..." before black's only when asked for, like after a comment
+pub(crate) fn move_number(board: &Board, black_too: bool) -> Option<String> {
+    match board.side_to_move {
+        Color::White => Some(format!("{}.", board.fullmove_number)),
+        Color::Black if black_too => Some(format!("{}...", board.fullmove_number)),
+        Color::Black => None,
+    }
+}
+
+// the standard keeps lines under 80 characters. move numbers stay with their moves, and variations
+// hug their brackets like "(1. d4)"
+pub(crate) fn wrap_movetext(tokens: &[String]) -> String {
+    let mut words: Vec<String> = Vec::new();
+    for token in tokens {
+        match words.last_mut() {
+            Some(last) if last.ends_with('(') || token == ")" => last.push_str(token),
+            Some(last) if last.ends_with('.') => {
+                last.push(' ');
+                last.push_str(token);
+            },
+            _ => words.push(token.clone()),
+        }
+    }
+
+    let mut movetext = String::new();
+    let mut line = String::new();
+    for word in words {
+        if !line.is_empty() && line.len() + 1 + word.len() > 79 {
+            movetext.push_str(&line);
+            movetext.push('\n');
+            line.clear();
+        } else if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&word);
+    }
+    movetext.push_str(&line);
+    movetext.push('\n');
+
+    movetext
+}
+
+pub(crate) fn tag(name: &str, value: &str) -> String {
+    format!("[{} \"{}\"]\n", name, value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A game read from PGN, with every move checked against the rules.
+#[derive(Debug, Clone)]
+pub struct PgnGame {
+    /// Every tag in the order it was written.
+    pub tags: Vec<(String, String)>,
+    /// Where the game started, from the `FEN` tag or the usual setup.
+    pub start: Board,
+    /// The mainline, variations hang off the moves they're alternatives to.
+    pub moves: Vec<PgnMove>,
+    /// Comments with no move after them to go with, only found in a game without moves.
+    pub comments: Vec<String>,
+    /// The result at the end of the movetext, or the `Result` tag when the movetext leaves it out.
+    pub result: String,
+}
+
+/// A move from a PGN game or variation, along with whatever was written around it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgnMove {
+    pub mv: Move,
+    /// The move as it was written.
+    pub san: String,
+    /// Numeric annotation glyphs, suffixes like `!?` are turned into the number they stand for.
+    pub nags: Vec<u8>,
+    /// Comments in front of the move, only found at the start of a game or variation.
+    pub comments_before: Vec<String>,
+    /// Comments after the move.
+    pub comments: Vec<String>,
+    /// Lines that could have been played instead of this move.
+    pub variations: Vec<PgnVariation>,
+}
+
+/// A line that could have been played instead of a move.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgnVariation {
+    /// The moves, starting with the alternative.
+    pub moves: Vec<PgnMove>,
+    /// Comments with no move after them to go with, only found in a variation without moves.
+    pub comments: Vec<String>,
+}
+
+impl PgnGame {
+    /// The value of a tag, if the game has it.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str())
+    }
+
+    /// The position at the end of the mainline, with its moves on the move stack so they can be undone.
+    pub fn board(&self) -> Board {
+        let mut board = self.start.clone();
+        for pgn_move in &self.moves {
+            board.push_move(pgn_move.mv);
+        }
+        board
+    }
+}
+
+/// Why some PGN couldn't be read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PgnError {
+    Syntax(String),
+    Fen(FenError),
+    /// A move that can't be played. `ply` counts half moves from the start of the game, along the
+    /// variation the move is in.
+    IllegalMove { ply: usize, san: String, error: MoveError },
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PgnError::Syntax(message) => write!(f, "{}", message),
+            PgnError::Fen(e) => write!(f, "invalid FEN tag: {}", e),
+            PgnError::IllegalMove { ply, san, error } => write!(f, "{} at ply {} can't be played: {}", san, ply, error),
+        }
+    }
+}
+
+/// Reads every game in some PGN text, stopping at the first one that's broken.
+pub fn parse_pgn(text: &str) -> Result<Vec<PgnGame>, PgnError> {
+    let mut tokens = tokenize(text)?.into_iter().peekable();
+    let mut games = Vec::new();
+    while tokens.peek().is_some() {
+        games.push(read_game(&mut tokens)?);
+    }
+    Ok(games)
+}
+
+/// Reads the first game in some PGN text.
+pub fn from_pgn(text: &str) -> Result<PgnGame, PgnError> {
+    let mut tokens = tokenize(text)?.into_iter().peekable();
+    if tokens.peek().is_none() {
+        return Err(PgnError::Syntax("there is no game to read".to_string()));
+    }
+    read_game(&mut tokens)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    Open,
+    Close,
+    Result(String),
+    Move(String),
+}
+
+type Tokens = Peekable<IntoIter<Token>>;
+
+fn read_game(tokens: &mut Tokens) -> Result<PgnGame, PgnError> {
+    let mut tags = Vec::new();
+    while let Some(Token::Tag(name, value)) = tokens.next_if(|token| matches!(token, Token::Tag(..))) {
+        tags.push((name, value));
+    }
+
+    let start = match tags.iter().find(|(name, _)| name == "FEN") {
+        Some((_, fen)) => Board::from_fen(fen).map_err(PgnError::Fen)?,
+        None => Board::new(),
+    };
+
+    let mut board = start.clone();
+    let (moves, comments) = read_line(tokens, &mut board)?;
+    if tokens.next_if_eq(&Token::Close).is_some() {
+        return Err(PgnError::Syntax("found a ')' without a variation to close".to_string()));
+    }
+
+    let result = match tokens.next_if(|token| matches!(token, Token::Result(_))) {
+        Some(Token::Result(result)) => result,
+        _ => tags.iter().find(|(name, _)| name == "Result").map_or("*".to_string(), |(_, result)| result.clone()),
+    };
+
+    Ok(PgnGame { tags, start, moves, comments, result })
+}
+
+// plays the line out on the board as it goes, and leaves it played out. comments come back on their
+// own when there's no move in the line to hang them on
+fn read_line(tokens: &mut Tokens, board: &mut Board) -> Result<(Vec<PgnMove>, Vec<String>), PgnError> {
+    let mut line: Vec<PgnMove> = Vec::new();
+    let mut comments = Vec::new(); // ones before the first move, waiting for it
+
+    while tokens.peek().is_some_and(|token| !matches!(token, Token::Close | Token::Result(_) | Token::Tag(..))) {
+        match tokens.next() {
+            Some(Token::Comment(comment)) => match line.last_mut() {
+                Some(last) => last.comments.push(comment),
+                None => comments.push(comment),
+            },
+            Some(Token::Nag(nag)) => match line.last_mut() {
+                Some(last) => last.nags.push(nag),
+                None => return Err(PgnError::Syntax(format!("found ${} before any move", nag))),
+            },
+            Some(Token::Open) => {
+                let Some(last) = line.last_mut() else {
+                    return Err(PgnError::Syntax("found a variation before any move".to_string()));
+                };
+
+                // a variation replaces the move before it
+                board.pop_move();
+                let (moves, comments) = read_line(tokens, board)?;
+                for _ in &moves {
+                    board.pop_move();
+                }
+                board.push_move(last.mv);
+
+                if tokens.next_if_eq(&Token::Close).is_none() {
+                    return Err(PgnError::Syntax("a variation is missing its ')'".to_string()));
+                }
+                last.variations.push(PgnVariation { moves, comments });
+            },
+            Some(Token::Move(san)) => {
+                let ply = board.move_stack.len() + 1;
+                let mv = match parse_san(board, &san) {
+                    Ok(mv) => mv,
+                    Err(error) => return Err(PgnError::IllegalMove { ply, san, error }),
+                };
+
+                board.push_move(mv);
+                line.push(PgnMove {
+                    mv,
+                    san,
+                    nags: Vec::new(),
+                    comments_before: mem::take(&mut comments),
+                    comments: Vec::new(),
+                    variations: Vec::new(),
+                });
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    Ok((line, comments))
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, PgnError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line_start = true;
+
+    while let Some(c) = chars.next() {
+        if c == '%' && line_start { // escaped line, meant for some other program
+            while chars.next_if(|&c| c != '\n').is_some() {}
+            continue;
+        }
+        line_start = c == '\n';
+
+        match c {
+            c if c.is_whitespace() => {},
+            '[' => tokens.push(read_tag(&mut chars)?),
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => comment.push(c),
+                        None => return Err(PgnError::Syntax("a comment is missing its '}'".to_string())),
+                    }
+                }
+                tokens.push(Token::Comment(comment.split_whitespace().collect::<Vec<_>>().join(" ")));
+            },
+            ';' => { // comment to the end of the line
+                let mut comment = String::new();
+                while let Some(c) = chars.next_if(|&c| c != '\n') {
+                    comment.push(c);
+                }
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            },
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            '*' => tokens.push(Token::Result("*".to_string())),
+            '$' => {
+                let mut digits = String::new();
+                while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                    digits.push(c);
+                }
+                match digits.parse() {
+                    Ok(nag) => tokens.push(Token::Nag(nag)),
+                    Err(_) => return Err(PgnError::Syntax(format!("'${}' is not an annotation glyph", digits))),
+                }
+            },
+            '!' | '?' => {
+                let mut glyph = c.to_string();
+                while let Some(c) = chars.next_if(|&c| c == '!' || c == '?') {
+                    glyph.push(c);
+                }
+                match GLYPHS.iter().position(|&known| known == glyph) {
+                    Some(index) => tokens.push(Token::Nag(index as u8 + 1)),
+                    None => return Err(PgnError::Syntax(format!("'{}' is not an annotation", glyph))),
+                }
+            },
+            c if is_symbol_char(c) => {
+                let mut symbol = c.to_string();
+                while let Some(c) = chars.next_if(|&c| is_symbol_char(c)) {
+                    symbol.push(c);
+                }
+
+                if matches!(symbol.as_str(), "1-0" | "0-1" | "1/2-1/2") {
+                    tokens.push(Token::Result(symbol));
+                    continue;
+                }
+
+                // move numbers like "12." or "12...", sometimes stuck to the move
+                let after_number = symbol.trim_start_matches(|c: char| c.is_ascii_digit());
+                let san = if after_number.is_empty() || after_number.starts_with('.') {
+                    after_number.trim_start_matches('.')
+                } else {
+                    &symbol
+                };
+                if !san.is_empty() {
+                    tokens.push(Token::Move(san.to_string()));
+                }
+            },
+            c => return Err(PgnError::Syntax(format!("unexpected '{}'", c))),
+        }
+    }
+
+    Ok(tokens)
+}
+
+// the suffixes that stand for $1 to $6
+const GLYPHS: [&str; 6] = ["!", "?", "!!", "??", "!?", "?!"];
+
+fn is_symbol_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "+#=-/.:_".contains(c)
+}
+
+fn read_tag(chars: &mut Peekable<Chars>) -> Result<Token, PgnError> { // after the '['
+    let malformed = |what: &str| PgnError::Syntax(format!("malformed tag, {}", what));
+
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+    let mut name = String::new();
+    while let Some(c) = chars.next_if(|&c| c.is_ascii_alphanumeric() || c == '_') {
+        name.push(c);
+    }
+    if name.is_empty() {
+        return Err(malformed("it has no name"));
+    }
+
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+    if chars.next() != Some('"') {
+        return Err(malformed(&format!("{} has no quoted value", name)));
+    }
+
+    let mut value = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => break,
+            Some('\\') => match chars.next() {
+                Some(c) => value.push(c),
+                None => return Err(malformed(&format!("the value of {} never ends", name))),
+            },
+            Some(c) => value.push(c),
+            None => return Err(malformed(&format!("the value of {} never ends", name))),
+        }
+    }
+
+    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+    if chars.next() != Some(']') {
+        return Err(malformed(&format!("{} is missing its ']'", name)));
+    }
+
+    Ok(Token::Tag(name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    fn play(board: &mut Board, moves: &[&str]) {
+        for san in moves {
+            let mv = parse_san(board, san).unwrap();
+            board.push_move(mv);
+        }
+    }
+
+    #[test]
+    fn exports_a_finished_game() {
+        let mut board = Board::new();
+        play(&mut board, &["f3", "e5", "g4", "Qh4"]);
+
+        let tags = PgnTags {
+            event: "Casual game".to_string(),
+            white: "Fool".to_string(),
+            black: "Theo \"the engine\"".to_string(),
+            result: result_of(board.status()).to_string(),
+            ..PgnTags::default()
+        };
+
+        assert_eq!(to_pgn(&board, &tags), concat!(
+            "[Event \"Casual game\"]\n",
+            "[Site \"?\"]\n",
+            "[Date \"????.??.??\"]\n",
+            "[Round \"?\"]\n",
+            "[White \"Fool\"]\n",
+            "[Black \"Theo \\\"the engine\\\"\"]\n",
+            "[Result \"0-1\"]\n",
+            "\n",
+            "1. f3 e5 2. g4 Qh4# 0-1\n",
+        ));
+    }
+
+    #[test]
+    fn games_from_a_position_carry_their_fen() {
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 0 30";
+        let mut board = Board::from_fen(fen).unwrap();
+        play(&mut board, &["Kd7", "e4", "Kc6"]);
+
+        let pgn = to_pgn(&board, &PgnTags::default());
+
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 30\"]\n"));
+        assert!(pgn.ends_with("\n30... Kd7 31. e4 Kc6 *\n"));
+    }
+
+    #[test]
+    fn long_games_are_wrapped() {
+        let mut board = Board::new();
+        for _ in 0..10 {
+            play(&mut board, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+        }
+
+        let pgn = to_pgn(&board, &PgnTags::default());
+        let movetext: Vec<&str> = pgn.split("\n\n").nth(1).unwrap().lines().collect();
+
+        assert!(movetext.len() > 1);
+        assert!(movetext.iter().all(|line| line.len() < 80));
+    }
+
+    #[test]
+    fn reads_tags_comments_and_variations() {
+        let pgn = r#"
+[Event "Club \"open\""]
+[Site "?"]
+[Result "1-0"]
+
+% a line for some other program
+{Before the game} 1. e4 $1 e5 2.Nf3!? (2. f4 exf4 (2... d5) 3. Nf3) Nc6 ; the usual
+3. Bb5 {The Ruy Lopez} a6 1-0
+"#;
+        let game = from_pgn(pgn).unwrap();
+
+        assert_eq!(game.tag("Event"), Some("Club \"open\""));
+        assert_eq!(game.result, "1-0");
+
+        let sans: Vec<&str> = game.moves.iter().map(|m| m.san.as_str()).collect();
+        assert_eq!(sans, ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6"]);
+        assert_eq!(game.moves[0].comments_before, ["Before the game"]);
+        assert_eq!(game.moves[0].nags, [1]);
+        assert_eq!(game.moves[2].nags, [5]);
+        assert_eq!(game.moves[3].comments, ["the usual"]);
+        assert_eq!(game.moves[4].comments, ["The Ruy Lopez"]);
+
+        let kings_gambit = &game.moves[2].variations[0];
+        assert_eq!(kings_gambit.moves.len(), 3);
+        assert_eq!(kings_gambit.moves[1].variations[0].moves[0].san, "d5");
+
+        assert_eq!(game.board().to_fen(), "r1bqkbnr/1ppp1ppp/p1n5/1B2p3/4P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 0 4");
+    }
+
+    #[test]
+    fn keeps_comments_without_a_move() {
+        let game = from_pgn("{Game abandoned} 1-0").unwrap();
+        assert!(game.moves.is_empty());
+        assert_eq!(game.comments, ["Game abandoned"]);
+        assert_eq!(game.result, "1-0");
+
+        let game = from_pgn("1. e4 ({Anything but} {d4}) e5 *").unwrap();
+        assert_eq!(game.moves[0].variations[0], PgnVariation { moves: Vec::new(), comments: vec!["Anything but".to_string(), "d4".to_string()] });
+        assert!(game.moves[0].comments.is_empty());
+    }
+
+    #[test]
+    fn reports_the_illegal_ply() {
+        let error = from_pgn("1. e4 e5 2. Nf3 Nc6 3. Bb5 Ke6 4. Bxc6 *").unwrap_err();
+        assert!(matches!(error, PgnError::IllegalMove { ply: 6, ref san, .. } if san == "Ke6"));
+
+        let error = from_pgn("1. e4 (1. d4 d5 2. Bf4 Qxd4 Nd2) e5 *").unwrap_err();
+        assert!(matches!(error, PgnError::IllegalMove { ply: 4, .. }));
+    }
+
+    #[test]
+    fn reads_games_starting_from_a_fen() {
+        let pgn = "[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 30\"]\n\n30... Kd7 31. e4 *";
+        let game = from_pgn(pgn).unwrap();
+
+        assert_eq!(game.start.to_fen(), "4k3/8/8/8/8/8/4P3/4K3 b - - 0 30");
+        assert_eq!(game.moves.len(), 2);
+    }
+
+    #[test]
+    fn written_games_read_back() {
+        let mut board = Board::new();
+        play(&mut board, &["d4", "d5", "c4", "dxc4", "e4", "b5", "a4", "c6", "axb5", "cxb5", "Qf3"]);
+        let tags = PgnTags { white: "A".to_string(), black: "B".to_string(), ..PgnTags::default() };
+
+        let mut pgn = to_pgn(&board, &tags);
+        pgn.push('\n');
+        pgn.push_str(&to_pgn(&Board::new(), &tags));
+
+        let games = parse_pgn(&pgn).unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].tag("Black"), Some("B"));
+        assert_eq!(games[0].board().to_fen(), board.to_fen());
+        assert!(games[1].moves.is_empty());
+    }
+
+    #[test]
+    fn rejects_broken_syntax() {
+        assert!(matches!(from_pgn("1. e4 (1. d4"), Err(PgnError::Syntax(_))));
+        assert!(matches!(from_pgn("1. e4 {unfinished"), Err(PgnError::Syntax(_))));
+        assert!(matches!(from_pgn("[Event \"x\"\n1. e4"), Err(PgnError::Syntax(_))));
+        assert!(matches!(from_pgn("[FEN \"8/8/8/8 w - - 0 1\"] *"), Err(PgnError::Fen(_))));
+    }
+}
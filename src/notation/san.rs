@@ -0,0 +1,252 @@
+//! Standard Algebraic Notation, the `Nf3`, `exd5`, `O-O` and `e8=Q+` everyone writes moves in.
+
+use crate::Board;
+use crate::board::board::Piece;
+use crate::board::move_generation::generate_valid_moves;
+use crate::board::square::Square;
+use crate::moves::{piece_from_letter, piece_letter, Move, MoveError};
+
+/// Writes a legal move for the side to move on `board` in SAN, disambiguated only as far as it
+/// needs to be and with `+` or `#` when it gives check or mate.
+pub fn to_san(board: &Board, mv: Move) -> String {
+    let mut san = match mv.flags() {
+        Move::KING_CASTLE => "O-O".to_string(),
+        Move::QUEEN_CASTLE => "O-O-O".to_string(),
+        _ => describe(board, mv),
+    };
+
+    let mut after = board.clone();
+    after.make_move(mv);
+    if after.in_check(after.side_to_move) {
+        san.push(if generate_valid_moves(&after).is_empty() { '#' } else { '+' });
+    }
+
+    san
+}
+
+fn describe(board: &Board, mv: Move) -> String { // everything but castling and the check suffix
+    let (from, to) = (mv.from(), mv.to());
+    let (piece, _) = board.piece_at(from).expect("to_san needs a piece on the from square");
+    let mut san = String::new();
+
+    if piece == Piece::P {
+        if mv.is_capture() {
+            san.push(file_letter(from));
+        }
+    } else {
+        san.push(piece_letter(piece));
+
+        // the other pieces of the same kind that could also go there, the file tells them apart if it
+        // can, then the rank, then it takes both
+        let rivals: Vec<Square> = generate_valid_moves(board)
+            .into_iter()
+            .filter(|other| other.to() == to && other.from() != from && board.piece_at(other.from()).map(|(p, _)| p) == Some(piece))
+            .map(|other| other.from())
+            .collect();
+
+        if !rivals.is_empty() {
+            if rivals.iter().all(|rival| rival.file() != from.file()) {
+                san.push(file_letter(from));
+            } else if rivals.iter().all(|rival| rival.rank() != from.rank()) {
+                san.push_str(&(from.rank() + 1).to_string());
+            } else {
+                san.push_str(&from.to_string());
+            }
+        }
+    }
+
+    if mv.is_capture() {
+        san.push('x');
+    }
+    san.push_str(&to.to_string());
+
+    if let Some(promotion) = mv.promotion() {
+        san.push('=');
+        san.push(piece_letter(promotion));
+    }
+
+    san
+}
+
+fn file_letter(square: Square) -> char {
+    (b'a' + square.file() as u8) as char
+}
+
+/// Finds the legal move a piece of SAN refers to. It's forgiving about the details: check marks,
+/// annotations like `!?`, `0-0` for `O-O`, a missing `x` or `=`, a lowercase promotion piece and
+/// more disambiguation than needed (`Ng1f3`, even `e2e4`) are all fine.
+pub fn parse_san(board: &Board, san: &str) -> Result<Move, MoveError> {
+    let malformed = || MoveError::MalformedNotation(san.to_string());
+    let text = san.trim().trim_end_matches(['+', '#', '!', '?']);
+    let text = text.strip_suffix("e.p.").unwrap_or(text).trim_end();
+
+    if !text.is_ascii() || text.is_empty() {
+        return Err(malformed());
+    }
+
+    let castle = text.replace('0', "O").to_ascii_uppercase();
+    if castle == "O-O" || castle == "O-O-O" {
+        let flags = if castle == "O-O" { Move::KING_CASTLE } else { Move::QUEEN_CASTLE };
+        return generate_valid_moves(board)
+            .into_iter()
+            .find(|mv| mv.flags() == flags)
+            .ok_or(MoveError::CastlingNotAllowed);
+    }
+
+    // pieces always get a capital, a lowercase b is the b-file
+    let (piece, mut rest) = match text.chars().next().and_then(piece_from_letter) {
+        Some(piece) if piece != Piece::P => (piece, &text[1..]),
+        _ => (Piece::P, text),
+    };
+
+    if rest.is_empty() { // a piece letter and nothing else
+        return Err(malformed());
+    }
+
+    // a letter after the destination rank can only be what a pawn promotes to
+    let mut promotion = None;
+    if let Some((before, letter)) = rest.split_at_checked(rest.len() - 1) {
+        let before = before.trim_end_matches('=');
+        if piece == Piece::P && letter.chars().all(|c| c.is_ascii_alphabetic()) && before.ends_with(|c: char| c.is_ascii_digit()) {
+            promotion = match piece_from_letter(letter.to_ascii_uppercase().chars().next().unwrap_or_default()) {
+                Some(promoted @ (Piece::Q | Piece::R | Piece::B | Piece::N)) => Some(promoted),
+                _ => return Err(MoveError::InvalidPromotion(letter.to_string())),
+            };
+            rest = before;
+        }
+    }
+
+    if rest.len() < 2 {
+        return Err(malformed());
+    }
+    let to: Square = rest[rest.len() - 2..].parse().map_err(|_| malformed())?;
+
+    // whatever is left before the destination can only be a capture mark and some of the from square
+    let hint = rest[..rest.len() - 2].trim_end_matches(['x', ':', '-']);
+    let (mut file, mut rank) = (None, None);
+    for c in hint.chars() {
+        match c {
+            'a'..='h' if file.is_none() && rank.is_none() => file = Some((c as u8 - b'a') as usize),
+            '1'..='8' if rank.is_none() => rank = Some((c as u8 - b'1') as usize),
+            _ => return Err(malformed()),
+        }
+    }
+
+    let candidates: Vec<Move> = generate_valid_moves(board)
+        .into_iter()
+        .filter(|mv| {
+            mv.to() == to
+                && board.piece_at(mv.from()).map(|(p, _)| p) == Some(piece)
+                && file.is_none_or(|file| mv.from().file() == file)
+                && rank.is_none_or(|rank| mv.from().rank() == rank)
+                && !matches!(mv.flags(), Move::KING_CASTLE | Move::QUEEN_CASTLE)
+        })
+        .collect();
+
+    match (candidates.as_slice(), promotion) {
+        ([], _) => Err(MoveError::NoMatchingMove(san.to_string())),
+        ([mv], None) => Ok(*mv),
+        ([mv, ..], None) if mv.promotion().is_some() => Err(MoveError::PromotionPieceNeeded { from: mv.from(), to }),
+        (_, None) => Err(MoveError::AmbiguousMove(san.to_string())),
+        ([mv, ..], Some(_)) if mv.promotion().is_none() => Err(MoveError::NotAPromotion { from: mv.from(), to }),
+        (_, Some(promoted)) => {
+            let matching: Vec<&Move> = candidates.iter().filter(|mv| mv.promotion() == Some(promoted)).collect();
+            match matching.as_slice() {
+                [mv] => Ok(**mv),
+                _ => Err(MoveError::AmbiguousMove(san.to_string())),
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::placement::play_move;
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    fn san_of(fen: &str, uci: &str) -> String {
+        let board = Board::from_fen(fen).unwrap();
+        let mv = generate_valid_moves(&board).into_iter().find(|mv| mv.to_string() == uci).unwrap();
+        to_san(&board, mv)
+    }
+
+    fn parsed(fen: &str, san: &str) -> Result<String, MoveError> {
+        parse_san(&Board::from_fen(fen).unwrap(), san).map(|mv| mv.to_string())
+    }
+
+    #[test]
+    fn writes_san() {
+        assert_eq!(san_of(crate::notation::fen::START_FEN, "g1f3"), "Nf3");
+        assert_eq!(san_of(crate::notation::fen::START_FEN, "e2e4"), "e4");
+        assert_eq!(san_of(KIWIPETE, "e1g1"), "O-O");
+        assert_eq!(san_of(KIWIPETE, "e1c1"), "O-O-O");
+        assert_eq!(san_of(KIWIPETE, "d5e6"), "dxe6");
+        assert_eq!(san_of(KIWIPETE, "e5f7"), "Nxf7");
+        assert_eq!(san_of(KIWIPETE, "f3f6"), "Qxf6");
+        assert_eq!(san_of("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"), "Ra8+");
+        assert_eq!(san_of("8/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e7e8q"), "e8=Q");
+        assert_eq!(san_of("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8"), "Ra8#");
+        assert_eq!(san_of("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), "exd6");
+    }
+
+    #[test]
+    fn disambiguates_only_as_far_as_needed() {
+        let knights = "4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1"; // both can reach d2
+        assert_eq!(san_of(knights, "b1d2"), "Nbd2");
+        assert_eq!(san_of(knights, "f1g3"), "Ng3");
+
+        let rooks = "4k3/R7/8/8/8/8/8/R3K3 w - - 0 1"; // same file, so the rank tells them apart
+        assert_eq!(san_of(rooks, "a1a4"), "R1a4");
+
+        let queens = "6k1/8/8/8/8/Q7/8/Q1Q4K w - - 0 1"; // a1 shares a file with a3 and a rank with c1
+        assert_eq!(san_of(queens, "a1b2"), "Qa1b2");
+    }
+
+    #[test]
+    fn reads_san_forgivingly() {
+        let start = crate::notation::fen::START_FEN;
+        assert_eq!(parsed(start, "Nf3"), Ok("g1f3".to_string()));
+        assert_eq!(parsed(start, "e4"), Ok("e2e4".to_string()));
+        assert_eq!(parsed(start, "Ng1f3!?"), Ok("g1f3".to_string()));
+        assert_eq!(parsed(start, "e2e4"), Ok("e2e4".to_string()));
+        assert_eq!(parsed(KIWIPETE, "0-0"), Ok("e1g1".to_string()));
+        assert_eq!(parsed(KIWIPETE, "O-O-O"), Ok("e1c1".to_string()));
+        assert_eq!(parsed(KIWIPETE, "dxe6"), Ok("d5e6".to_string()));
+        assert_eq!(parsed(KIWIPETE, "de6"), Ok("d5e6".to_string()));
+        assert_eq!(parsed(KIWIPETE, "Qxf6"), Ok("f3f6".to_string()));
+        assert_eq!(parsed(KIWIPETE, "Qf6+"), Ok("f3f6".to_string()));
+        assert_eq!(parsed("8/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e8=Q+"), Ok("e7e8q".to_string()));
+        assert_eq!(parsed("8/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e8n"), Ok("e7e8n".to_string()));
+        assert_eq!(parsed("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "exd6 e.p."), Ok("e5d6".to_string()));
+    }
+
+    #[test]
+    fn says_what_is_wrong_with_bad_san() {
+        let start = crate::notation::fen::START_FEN;
+        assert_eq!(parsed(start, "Nf4"), Err(MoveError::NoMatchingMove("Nf4".to_string())));
+        assert_eq!(parsed(start, "hello"), Err(MoveError::MalformedNotation("hello".to_string())));
+        assert_eq!(parsed(start, "N"), Err(MoveError::MalformedNotation("N".to_string())));
+        assert_eq!(parsed(start, "K+"), Err(MoveError::MalformedNotation("K+".to_string())));
+        assert_eq!(parsed(start, "O-O"), Err(MoveError::CastlingNotAllowed));
+        assert_eq!(parsed("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1", "Nd2"), Err(MoveError::AmbiguousMove("Nd2".to_string())));
+        assert!(matches!(parsed("8/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e8"), Err(MoveError::PromotionPieceNeeded { .. })));
+    }
+
+    #[test]
+    fn every_move_survives_a_round_trip() {
+        for fen in [crate::notation::fen::START_FEN, KIWIPETE, "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1"] {
+            let mut board = Board::from_fen(fen).unwrap();
+            for mv in generate_valid_moves(&board) {
+                assert_eq!(parse_san(&board, &to_san(&board, mv)), Ok(mv), "{} in {}", to_san(&board, mv), fen);
+            }
+
+            let first = generate_valid_moves(&board)[0];
+            play_move(&mut board, first).unwrap();
+            for mv in generate_valid_moves(&board) {
+                assert_eq!(parse_san(&board, &to_san(&board, mv)), Ok(mv));
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! UCI's long algebraic notation: the from and to squares and a promotion piece, like `e2e4`, `e7e8q`
+//! or `e1g1` for castling. It's what engines and GUIs pass between each other.
+
+use crate::Board;
+use crate::board::placement::find_legal_move;
+use crate::moves::{Move, MoveError};
+
+/// Writes a move the way UCI does.
+pub fn to_uci(mv: Move) -> String {
+    mv.to_string()
+}
+
+/// Reads a UCI move and finds the legal move it stands for, so captures, castling and en passant
+/// come out with the right flags.
+pub fn parse_uci(board: &Board, uci: &str) -> Result<Move, MoveError> {
+    let parsed: Move = uci.parse().map_err(|e| match e {
+        MoveError::MalformedNotation(_) => MoveError::MalformedUci(uci.to_string()),
+        e => e,
+    })?;
+    find_legal_move(board, parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Piece, Square};
+    use crate::board::move_generation::generate_valid_moves;
+
+    fn sq(name: &str) -> Square {
+        name.parse().unwrap()
+    }
+
+    #[test]
+    fn reads_every_kind_of_move() {
+        let board = Board::from_fen("r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1").unwrap();
+
+        assert!(parse_uci(&board, "e1g1").unwrap().flags() == Move::KING_CASTLE);
+        assert!(parse_uci(&board, "e1c1").unwrap().flags() == Move::QUEEN_CASTLE);
+        assert!(parse_uci(&board, "e5d6").unwrap().is_en_passant());
+        assert_eq!(parse_uci(&board, "b7a8n").unwrap().promotion(), Some(Piece::N));
+        assert!(parse_uci(&board, "b7a8N").unwrap().is_capture());
+        assert!(parse_uci(&Board::new(), "e2e4").unwrap().is_double_pawn_push());
+    }
+
+    #[test]
+    fn writes_what_it_reads() {
+        let board = Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+
+        for mv in generate_valid_moves(&board) {
+            assert_eq!(parse_uci(&board, &to_uci(mv)), Ok(mv));
+        }
+    }
+
+    #[test]
+    fn explains_bad_moves() {
+        let board = Board::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+
+        assert_eq!(parse_uci(&board, "e2"), Err(MoveError::MalformedUci("e2".to_string())));
+        assert_eq!(parse_uci(&board, "e1 e2"), Err(MoveError::MalformedUci("e1 e2".to_string())));
+        assert_eq!(parse_uci(&board, "e1e9"), Err(MoveError::InvalidSquare("e9".to_string())));
+        assert_eq!(parse_uci(&board, "b7b8k"), Err(MoveError::InvalidPromotion("k".to_string())));
+        assert_eq!(parse_uci(&board, "b7b8"), Err(MoveError::PromotionPieceNeeded { from: sq("b7"), to: sq("b8") }));
+        assert_eq!(parse_uci(&board, "e1e2q"), Err(MoveError::NotAPromotion { from: sq("e1"), to: sq("e2") }));
+        assert_eq!(parse_uci(&board, "e1g1"), Err(MoveError::CastlingNotAllowed));
+    }
+}
@@ -1,26 +1,15 @@
 use std::io::{stdout, stdin, Write};
 use colored::Colorize;
 
-use crate::{board::board::Piece, Board};
-
-pub fn parse_position(pos: &str) -> (usize, usize) {
-    let chars: Vec<char> = pos.chars().collect();
-    if chars.len() != 2 {
-        panic!("Invalid position: {}", pos);
-    }
-
-    let col = (chars[0] as u8 - b'a') as usize;
-    let row = (8 - (chars[1] as u8 - b'0')) as usize;
-
-    (row, col)
-}
+use theo::board::status::{DrawReason, GameStatus};
+use theo::{Board, Color, Square};
 
 pub fn display_board(board: &Board) {
-    for row in board.squares.iter() {
-        for square in row.iter() {
-            match square.piece {
-                Some((piece, true)) => print!("{:?}W ", piece),
-                Some((piece, false)) => print!("{:?}B ", piece),
+    for rank in (0..8).rev() { // rank 8 at the top, from white's side of the board
+        for file in 0..8 {
+            match board.piece_at(Square::new(rank * 8 + file)) {
+                Some((piece, Color::White)) => print!("{:?}W ", piece),
+                Some((piece, Color::Black)) => print!("{:?}B ", piece),
                 None => print!("-- "),
             }
         }
@@ -28,36 +17,33 @@ pub fn display_board(board: &Board) {
     }
 }
 
-pub fn get_user_input() -> String {
-    let mut input = String::new();
-    print!("Enter your move: ");
-    stdout().flush().unwrap();
-    stdin()
-        .read_line(&mut input)
-        .expect("Failed to read line");
-    input.trim().to_string()
+pub fn display_game_result(status: GameStatus) {
+    match status {
+        GameStatus::Checkmate(Color::White) => println!("{}", "Checkmate! White wins.".green().bold()),
+        GameStatus::Checkmate(Color::Black) => println!("{}", "Checkmate! Black wins.".red().bold()),
+        GameStatus::Stalemate => println!("{}", "Stalemate! The game is a draw.".yellow().bold()),
+        GameStatus::Draw(reason) => println!("{} {}", "Draw!".yellow().bold(), describe_draw(reason)),
+        GameStatus::Ongoing => {},
+    }
 }
 
-pub fn choose_promotion_piece() -> Result<Piece, String> {
-    let mut input = String::new();
-    println!("{}", "You're about to promote a pawn!\n
-        1. R\n
-        2. B\n
-        3. N\n
-        4. Q".green().bold());
+pub fn describe_draw(reason: DrawReason) -> &'static str {
+    match reason {
+        DrawReason::FiftyMoveRule => "Fifty moves without a capture or pawn move.",
+        DrawReason::SeventyFiveMoveRule => "Seventy-five moves without a capture or pawn move.",
+        DrawReason::ThreefoldRepetition => "The same position has appeared three times.",
+        DrawReason::FivefoldRepetition => "The same position has appeared five times.",
+        DrawReason::InsufficientMaterial => "Neither side has enough material to mate.",
+    }
+}
 
-    print!("Please enter your choice: ");
-    stdout().flush().unwrap();
-    stdin()
-        .read_line(&mut input)
-        .expect("Failed to read line"); // TODO: add error handling
-    input = input.trim().to_string();
+pub fn get_user_input(prompt: &str) -> Option<String> { // None once stdin is closed or can't be read
+    let mut input = String::new();
+    print!("{}", prompt);
+    let _ = stdout().flush();
 
-    match input.to_string().as_str() {
-        "1" => Ok(Piece::R),
-        "2" => Ok(Piece::B),
-        "3" => Ok(Piece::N),
-        "4" => Ok(Piece::Q),
-        _ => Err("Invalid input to choose promotion piece".to_string()),
+    match stdin().read_line(&mut input) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(input.trim().to_string()),
     }
 }
@@ -1 +1,2 @@
-pub mod board;
\ No newline at end of file
+pub mod board;
+pub mod pgn;
@@ -0,0 +1,65 @@
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use colored::Colorize;
+
+use theo::Board;
+use theo::notation::pgn::{from_pgn, to_pgn, PgnTags};
+
+pub fn save_game(board: &Board, result: &str, path: &str) {
+    let tags = PgnTags {
+        event: "Casual game".to_string(),
+        site: "Theo".to_string(),
+        date: today(),
+        white: "Player".to_string(),
+        black: "Theo".to_string(),
+        result: result.to_string(),
+        ..PgnTags::default()
+    };
+
+    match fs::write(path, to_pgn(board, &tags)) {
+        Ok(()) => println!("Saved the game to {}", path),
+        Err(e) => println!("{} {}", "Could not save the game:".red().bold(), e),
+    }
+}
+
+pub fn load_game(path: &str) -> Option<Board> { // the position at the end of the first game in the file
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            println!("{} {}", "Could not read the game:".red().bold(), e);
+            return None;
+        },
+    };
+
+    match from_pgn(&text) {
+        Ok(game) => {
+            println!("Loaded {} moves from {}", game.moves.len(), path);
+            Some(game.board())
+        },
+        Err(e) => {
+            println!("{} {}", "Could not load the game:".red().bold(), e);
+            None
+        },
+    }
+}
+
+fn today() -> String { // the UTC date in PGN's YYYY.MM.DD form
+    let days = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => (elapsed.as_secs() / 86_400) as i64,
+        Err(_) => return "????.??.??".to_string(),
+    };
+
+    // days since 1970-01-01 to a civil date, counting in 400 year eras that start on march 1st
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153; // 0 is march
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
@@ -0,0 +1,71 @@
+use theo::Board;
+use theo::notation::fen::START_FEN;
+
+// reference positions and node counts from https://www.chessprogramming.org/Perft_Results
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+const POSITION_4_MIRRORED: &str = "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1";
+const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+const POSITION_6: &str = "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+fn check(fen: &str, expected: &[u64]) {
+    let mut board = Board::from_fen(fen).unwrap();
+
+    for (depth, &nodes) in expected.iter().enumerate() {
+        assert_eq!(board.perft(depth as u32 + 1), nodes, "perft({}) of {}", depth + 1, fen);
+    }
+
+    assert_eq!(board.to_fen(), Board::from_fen(fen).unwrap().to_fen(), "perft left {} changed", fen);
+}
+
+#[test]
+fn start_position() {
+    check(START_FEN, &[20, 400, 8_902, 197_281]);
+}
+
+#[test]
+fn kiwipete() {
+    check(KIWIPETE, &[48, 2_039, 97_862]);
+}
+
+#[test]
+fn position_3() {
+    check(POSITION_3, &[14, 191, 2_812, 43_238]);
+}
+
+#[test]
+fn position_4() {
+    check(POSITION_4, &[6, 264, 9_467]);
+    check(POSITION_4_MIRRORED, &[6, 264, 9_467]);
+}
+
+#[test]
+fn position_5() {
+    check(POSITION_5, &[44, 1_486, 62_379]);
+}
+
+#[test]
+fn position_6() {
+    check(POSITION_6, &[46, 2_079, 89_890]);
+}
+
+#[test]
+fn divide_adds_up_to_perft() {
+    let mut board = Board::from_fen(KIWIPETE).unwrap();
+    let divided = board.divide(2);
+
+    assert_eq!(divided.len(), 48);
+    assert_eq!(divided.iter().map(|(_, nodes)| nodes).sum::<u64>(), 2_039);
+}
+
+#[test]
+#[ignore] // millions of nodes, run with `cargo test --release -- --ignored`
+fn deep() {
+    check(START_FEN, &[20, 400, 8_902, 197_281, 4_865_609]);
+    check(KIWIPETE, &[48, 2_039, 97_862, 4_085_603]);
+    check(POSITION_3, &[14, 191, 2_812, 43_238, 674_624, 11_030_083]);
+    check(POSITION_4, &[6, 264, 9_467, 422_333, 15_833_292]);
+    check(POSITION_5, &[44, 1_486, 62_379, 2_103_487]);
+    check(POSITION_6, &[46, 2_079, 89_890, 3_894_594]);
+}